@@ -0,0 +1,141 @@
+//! Latency of the fast endpoint, while the single worker of the server handles the slow
+//! synchronous requests, with and without the blocking threads:
+//!
+//! ```sh
+//! cargo bench --bench blocking
+//! ```
+
+use actix_web::http::Method;
+use actix_web::{App, HttpServer};
+use exonum::node::ApiSender;
+use exonum::storage::MemoryDB;
+use futures::future;
+
+use std::net::TcpListener;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use api_builder::actix_backend::configure;
+use api_builder::aggregator::ApiAggregator;
+use api_builder::client::ApiClient;
+use api_builder::error::Error;
+use api_builder::service::{
+    Service, ServiceApiContext, ServiceApiContextMut, ServiceApiInitializer,
+};
+
+/// Number of the concurrent requests to the slow endpoint.
+const SLOW_REQUESTS: usize = 16;
+/// Duration of the slow handler, e.g. the one merging the changes into the blockchain.
+const SLOW_HANDLER: Duration = Duration::from_millis(50);
+/// Number of the sequential requests to the fast endpoint.
+const PINGS: usize = 20;
+
+async fn ping(_: ServiceApiContext, _: ()) -> Result<String, Error> {
+    Ok("pong".to_owned())
+}
+
+struct BenchService;
+
+impl Service for BenchService {
+    fn service_name(&self) -> &str {
+        "bench"
+    }
+
+    fn initialize_api(&self, initializer: &mut ServiceApiInitializer) {
+        initializer
+            .public_api()
+            .endpoint(
+                "slow",
+                |_: &ServiceApiContextMut, _: ()| -> Result<(), Error> {
+                    thread::sleep(SLOW_HANDLER);
+                    Ok(())
+                },
+            )
+            .endpoint_async("ping", ping);
+    }
+}
+
+struct Report {
+    max_latency: Duration,
+    mean_latency: Duration,
+    total: Duration,
+}
+
+async fn run(blocking_threads: Option<usize>) -> Report {
+    let services: Vec<Box<dyn Service>> = vec![Box::new(BenchService)];
+    let apis = ApiAggregator::new(&services).unwrap().public_api();
+    let api_sender = ApiSender::new(futures01::sync::mpsc::channel(1).0);
+    let mut context = ServiceApiContextMut::with_database(MemoryDB::new(), api_sender);
+    if blocking_threads.is_some() {
+        context = context.with_blocking_handlers();
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let mut server = HttpServer::new(move || {
+        let context = context.clone();
+        let apis = apis.clone();
+        App::new().configure(move |config| configure(config, context, apis))
+    })
+    .workers(1);
+    if let Some(threads) = blocking_threads {
+        server = server.worker_max_blocking_threads(threads);
+    }
+    let server = server.listen(listener).unwrap().run();
+    let handle = server.handle();
+    actix_web::rt::spawn(server);
+
+    let client = ApiClient::new(format!("http://{}", address));
+    let slow = future::join_all(
+        (0..SLOW_REQUESTS)
+            .map(|_| client.request::<_, ()>(Method::POST, "/api/services/bench/slow", &())),
+    );
+    let pings = async {
+        // The slow requests should reach the server first.
+        actix_web::rt::time::sleep(Duration::from_millis(5)).await;
+        let mut latencies = Vec::with_capacity(PINGS);
+        for _ in 0..PINGS {
+            let start = Instant::now();
+            let _: String = client
+                .request(Method::GET, "/api/services/bench/ping", &())
+                .await
+                .unwrap();
+            latencies.push(start.elapsed());
+        }
+        latencies
+    };
+
+    let start = Instant::now();
+    let (slow, latencies) = future::join(slow, pings).await;
+    let total = start.elapsed();
+    for response in slow {
+        response.unwrap();
+    }
+    handle.stop(true).await;
+
+    Report {
+        max_latency: latencies.iter().max().cloned().unwrap_or_default(),
+        mean_latency: latencies.iter().sum::<Duration>() / PINGS as u32,
+        total,
+    }
+}
+
+#[actix_web::main]
+async fn main() {
+    println!(
+        "{} slow requests of {:?} and {} pings on the single worker",
+        SLOW_REQUESTS, SLOW_HANDLER, PINGS
+    );
+    let runs = vec![
+        ("in place", None),
+        ("4 blocking threads", Some(4)),
+        ("16 blocking threads", Some(16)),
+    ];
+    for (name, threads) in runs {
+        let report = run(threads).await;
+        println!(
+            "{:>20}: ping latency max {:?}, mean {:?}; total {:?}",
+            name, report.max_latency, report.mean_latency, report.total
+        );
+    }
+}
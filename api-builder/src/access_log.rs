@@ -0,0 +1,106 @@
+//! Access logs of the endpoints and the request IDs, which allow to correlate
+//! the log records of the same request.
+
+use http::{Method, StatusCode};
+use log::Level;
+use uuid::Uuid;
+
+use std::time::Instant;
+
+use crate::error::Error;
+
+/// Header with the ID of the request. The ID is generated if the client has not provided
+/// the valid one, and is returned in the same header of the response.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Maximum length of the request ID provided by the client.
+pub const MAX_REQUEST_ID_LEN: usize = 64;
+
+/// Target of the access log records, which allows to filter them with `RUST_LOG`.
+pub const LOG_TARGET: &str = "api_builder::access";
+
+/// Returns the request ID provided by the client or generates the new one.
+///
+/// The client ID is accepted only if it consists of at most `MAX_REQUEST_ID_LEN` ASCII
+/// alphanumeric characters, `-`, `_` and `.`, so it cannot forge the fields of the log record.
+pub fn request_id(header: Option<&str>) -> String {
+    match header {
+        Some(id) if is_valid_request_id(id) => id.to_owned(),
+        _ => Uuid::new_v4().to_string(),
+    }
+}
+
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+/// Record of the single request, which is logged once the response is ready.
+#[derive(Debug)]
+pub struct AccessLog {
+    /// Name of the endpoint, e.g. `block`.
+    endpoint: String,
+    /// Path of the request, e.g. `/api/services/explorer/block`.
+    path: String,
+    method: Method,
+    request_id: String,
+    start: Instant,
+}
+
+impl AccessLog {
+    /// Starts the timer of the request to the endpoint with the given name and path.
+    pub fn start(endpoint: &str, path: &str, method: Method, request_id: String) -> AccessLog {
+        AccessLog {
+            endpoint: endpoint.to_owned(),
+            path: path.to_owned(),
+            method,
+            request_id,
+            start: Instant::now(),
+        }
+    }
+
+    pub fn request_id(&self) -> &str {
+        &self.request_id
+    }
+
+    /// Logs the request with the response status and the error of the handler, if any.
+    /// Failed requests are logged with the `warn` level and internal errors with `error` one.
+    pub fn finish(self, status: StatusCode, error: Option<&Error>) {
+        let level = if status.is_server_error() {
+            Level::Error
+        } else if error.is_some() {
+            Level::Warn
+        } else {
+            Level::Info
+        };
+        let duration = self.start.elapsed().as_secs_f64() * 1000.0;
+        match error {
+            Some(error) => log::log!(
+                target: LOG_TARGET,
+                level,
+                "request_id={} endpoint={} path={} method={} status={} duration_ms={:.3} error={:?}",
+                self.request_id,
+                self.endpoint,
+                self.path,
+                self.method,
+                status.as_u16(),
+                duration,
+                error.message
+            ),
+            None => log::log!(
+                target: LOG_TARGET,
+                level,
+                "request_id={} endpoint={} path={} method={} status={} duration_ms={:.3}",
+                self.request_id,
+                self.endpoint,
+                self.path,
+                self.method,
+                status.as_u16(),
+                duration
+            ),
+        }
+    }
+}
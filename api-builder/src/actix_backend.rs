@@ -1,26 +1,32 @@
-use actix_web::{self, AsyncResponder, FromRequest, FutureResponse, HttpMessage, HttpRequest,
-                HttpResponse, Query};
-use futures::{Future, IntoFuture};
+use actix_web::dev::Server;
+use actix_web::http::header::{ContentType, HeaderMap, HeaderName, HeaderValue};
+use actix_web::http::{Method, StatusCode};
+use actix_web::web::{self, Bytes};
+use actix_web::{App, HttpRequest, HttpResponse, HttpServer, ResponseError};
+use futures::future::{self, FutureExt, LocalBoxFuture};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use serde_json::Value;
 
+use std::future::Future;
+use std::io;
 use std::sync::Arc;
 
-use service::{ServiceApiBackend, ServiceApiContext, ServiceApiContextMut};
-use {FutureResult, NamedFn, Result};
+use crate::batch::BatchHandler;
+use crate::error::Error;
+use crate::handler::{self, Headers, Request};
+use crate::service::{EndpointMeta, ServiceApiBackend, ServiceApiContext, ServiceApiContextMut};
+use crate::{Async, NamedFn, Result};
 
-pub type RawHandler = Fn(HttpRequest<ServiceApiContextMut>)
-        -> FutureResponse<HttpResponse, actix_web::Error>
-    + 'static
-    + Send
-    + Sync;
+/// Future of the JSON response body.
+pub type ResponseFuture = LocalBoxFuture<'static, Result<Vec<u8>>>;
 
-#[derive(Clone)]
-pub struct RequestHandler {
-    pub name: &'static str,
-    pub method: actix_web::http::Method,
-    pub inner: Arc<RawHandler>,
-}
+/// Future of the batch item response.
+pub type BatchFuture = LocalBoxFuture<'static, Result<Value>>;
+
+pub type RawHandler = handler::RawHandler<ResponseFuture>;
+
+pub type RequestHandler = handler::RequestHandler<ResponseFuture, BatchFuture>;
 
 #[derive(Default)]
 pub struct BackendBuilder {
@@ -34,18 +40,276 @@ impl ServiceApiBackend for BackendBuilder {
         self.handlers.push(handler);
         self
     }
+
+    fn finish(self) -> Vec<RequestHandler> {
+        self.handlers
+    }
 }
 
 impl BackendBuilder {
     pub fn new() -> BackendBuilder {
         BackendBuilder::default()
     }
+}
 
-    pub fn finish(self) -> Vec<RequestHandler> {
-        self.handlers
+impl Headers for HeaderMap {
+    fn get_str(&self, name: &str) -> Option<&str> {
+        self.get(name)
+            .map(|value| value.to_str().unwrap_or_default())
+    }
+}
+
+impl handler::Response for HttpResponse {
+    fn json(body: Vec<u8>) -> Self {
+        HttpResponse::Ok()
+            .content_type(ContentType::json())
+            .body(body)
+    }
+
+    fn error(error: &Error) -> Self {
+        error.error_response()
+    }
+
+    fn status(&self) -> StatusCode {
+        HttpResponse::status(self)
+    }
+
+    fn insert_header(&mut self, name: HeaderName, value: HeaderValue) {
+        self.headers_mut().insert(name, value);
+    }
+}
+
+/// Adapts the request and its body to the backend independent handling.
+fn adapt_request<'a>(request: &'a HttpRequest, body: &'a [u8]) -> Request<'a> {
+    Request {
+        method: request.method(),
+        path: request.path(),
+        query: request.query_string(),
+        headers: request.headers(),
+        body,
+    }
+}
+
+/// Mounts the given handlers to the service config of the scope. Each request is logged
+/// to the access log, the signed requests are verified and the response carries the ID
+/// of the request.
+pub fn mount_handlers(config: &mut web::ServiceConfig, handlers: Vec<RequestHandler>) {
+    for handler in handlers {
+        let name = handler.name;
+        let inner = handler.inner;
+        let route = web::method(handler.method).to(
+            move |context: web::Data<ServiceApiContextMut>, request: HttpRequest, body: Bytes| {
+                let request = adapt_request(&request, &body);
+                handler::handle::<HttpResponse, _>(
+                    name,
+                    &request,
+                    context.get_ref().clone(),
+                    &*inner,
+                )
+            },
+        );
+        config.route(&format!("/{}", name), route);
+    }
+}
+
+/// Configures an application with the handlers mounted under the `api/<prefix>` scopes,
+/// the `/_endpoints` listing of them and the `/batch` endpoint, which invokes them.
+pub fn configure(
+    config: &mut web::ServiceConfig,
+    context: ServiceApiContextMut,
+    apis: Vec<(String, Vec<RequestHandler>)>,
+) {
+    let endpoints = handler::endpoints(&apis);
+    let batch_handlers = Arc::new(handler::batch_handlers(&apis));
+
+    let mut scope = web::scope("/api");
+    for (prefix, handlers) in apis {
+        scope = scope.service(
+            web::scope(&format!("/{}", prefix))
+                .configure(move |config| mount_handlers(config, handlers)),
+        );
+    }
+
+    config
+        .app_data(web::Data::new(context))
+        .route(
+            "/_endpoints",
+            web::get().to(move || future::ready(HttpResponse::Ok().json(&endpoints))),
+        )
+        .route(
+            "/batch",
+            web::post().to(
+                move |context: web::Data<ServiceApiContextMut>,
+                      request: HttpRequest,
+                      body: Bytes| {
+                    let request = adapt_request(&request, &body);
+                    handler::handle_batch::<HttpResponse, _>(
+                        &request,
+                        context.get_ref().clone(),
+                        batch_handlers.clone(),
+                    )
+                },
+            ),
+        )
+        .service(scope);
+}
+
+/// Creates the HTTP server with the given APIs, the returned server should be awaited
+/// within the actix runtime. The synchronous handlers are run on the event loop unless
+/// the number of the blocking threads of each worker of the server is given, see
+/// `ServiceApiContextMut::with_blocking_handlers`.
+///
+/// # Panics
+///
+/// If the number of the blocking threads is zero.
+pub fn start_server(
+    listen_address: &str,
+    blocking_threads: Option<usize>,
+    context: ServiceApiContextMut,
+    apis: Vec<(String, Vec<RequestHandler>)>,
+) -> io::Result<Server> {
+    let context = match blocking_threads {
+        Some(threads) => {
+            assert!(
+                threads > 0,
+                "Number of the blocking threads should be positive"
+            );
+            context.with_blocking_handlers()
+        }
+        None => context,
+    };
+    let mut server = HttpServer::new(move || {
+        let context = context.clone();
+        let apis = apis.clone();
+        App::new().configure(move |config| configure(config, context, apis))
+    });
+    if let Some(threads) = blocking_threads {
+        server = server.worker_max_blocking_threads(threads);
+    }
+    Ok(server.bind(listen_address)?.run())
+}
+
+/// Calls the synchronous function with `web::block` if the blocking handlers are enabled
+/// for the context, or in place otherwise.
+fn call_sync<S, T, F>(blocking: bool, context: S, f: F) -> LocalBoxFuture<'static, Result<T>>
+where
+    S: Send + 'static,
+    T: Send + 'static,
+    F: FnOnce(&S) -> Result<T> + Send + 'static,
+{
+    if blocking {
+        block(move || f(&context))
+    } else {
+        future::ready(f(&context)).boxed_local()
     }
 }
 
+/// Calls the synchronous function on the blocking threads of the worker.
+fn block<T, F>(f: F) -> LocalBoxFuture<'static, Result<T>>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    let result = web::block(f);
+    async move {
+        result
+            .await
+            .map_err(|_| Error::internal("Handler has panicked"))?
+    }
+    .boxed_local()
+}
+
+/// Wraps the synchronous handler into the raw handler. The query is extracted and
+/// the result is serialized along with the call of the handler, so neither of them
+/// has to be `Send` if the handler is run on the blocking threads.
+fn create_sync_handler<S, Q, I, F>(
+    method: Method,
+    handler: Arc<F>,
+    into_context: fn(ServiceApiContextMut) -> S,
+) -> Arc<RawHandler>
+where
+    S: Send + 'static,
+    Q: DeserializeOwned + 'static,
+    I: Serialize + 'static,
+    F: Fn(&S, Q) -> Result<I> + 'static + Send + Sync,
+{
+    Arc::new(
+        move |context: ServiceApiContextMut, query: &str, body: &[u8]| {
+            let blocking = context.blocking;
+            let context = into_context(context);
+            if !blocking {
+                return future::ready(respond(&*handler, &context, &method, query, body))
+                    .boxed_local();
+            }
+            let handler = handler.clone();
+            let method = method.clone();
+            let (query, body) = (query.to_owned(), body.to_vec());
+            block(move || respond(&*handler, &context, &method, &query, &body))
+        },
+    )
+}
+
+/// Calls the synchronous handler with the query extracted from the request and returns
+/// the JSON of its result.
+fn respond<S, Q, I, F>(
+    handler: &F,
+    context: &S,
+    method: &Method,
+    query: &str,
+    body: &[u8],
+) -> Result<Vec<u8>>
+where
+    Q: DeserializeOwned,
+    I: Serialize,
+    F: Fn(&S, Q) -> Result<I>,
+{
+    let query = handler::extract_query(method, query, body)?;
+    serde_json::to_vec(&handler(context, query)?).map_err(Error::internal)
+}
+
+/// Calls the synchronous handler with the query of the batch item, see `create_sync_handler`.
+fn respond_batch<S, Q, I, F>(handler: &F, context: &S, query: Value) -> Result<Value>
+where
+    Q: DeserializeOwned,
+    I: Serialize,
+    F: Fn(&S, Q) -> Result<I>,
+{
+    let query = serde_json::from_value(query).map_err(Error::bad_request)?;
+    serde_json::to_value(handler(context, query)?).map_err(Error::internal)
+}
+
+/// Wraps the handler, which takes the mutable context and the extracted query,
+/// into the raw handler.
+fn create_raw_handler<Q, I, H, Fut>(method: Method, handler: H) -> Arc<RawHandler>
+where
+    Q: DeserializeOwned + 'static,
+    I: Serialize + 'static,
+    H: Fn(ServiceApiContextMut, Q) -> Fut + 'static + Send + Sync,
+    Fut: Future<Output = Result<I>> + 'static,
+{
+    Arc::new(
+        move |context: ServiceApiContextMut, query: &str, body: &[u8]| {
+            handler::call_handler(&handler, context, &method, query, body).boxed_local()
+        },
+    )
+}
+
+/// Wraps the handler into the handler of the batch items, which takes the query as JSON.
+fn create_batch_handler<S, Q, I, H, Fut>(
+    handler: H,
+) -> Arc<dyn Fn(S, Value) -> BatchFuture + Send + Sync>
+where
+    S: 'static,
+    Q: DeserializeOwned + 'static,
+    I: Serialize + 'static,
+    H: Fn(S, Q) -> Fut + 'static + Send + Sync,
+    Fut: Future<Output = Result<I>> + 'static,
+{
+    Arc::new(move |context: S, query: Value| {
+        handler::call_batch_handler(&handler, context, query).boxed_local()
+    })
+}
+
 impl<Q, I, F> From<NamedFn<ServiceApiContext, Q, I, Result<I>, F>> for RequestHandler
 where
     F: for<'r> Fn(&'r ServiceApiContext, Q) -> Result<I> + 'static + Send + Sync,
@@ -53,112 +317,98 @@ where
     I: Serialize + 'static,
 {
     fn from(f: NamedFn<ServiceApiContext, Q, I, Result<I>, F>) -> Self {
-        let handler = f.inner.f;
-        let index = move |request: HttpRequest<ServiceApiContextMut>|
-         -> Box<Future<Item=HttpResponse, Error=actix_web::Error>> {
-            let context = request.state();
-            let future = Query::from_request(&request, &())
-                .map(|query: Query<Q>| query.into_inner())
-                .and_then(|query| handler(context, query).map_err(From::from))
-                .and_then(|value| Ok(HttpResponse::Ok().json(value)))
-                .into_future();
-            Box::new(future)
+        let handler = Arc::new(f.inner.f);
+        let method = f.method.unwrap_or(Method::GET);
+        let batch_handler = handler.clone();
+        let batch = move |context: ServiceApiContext, query: Value| {
+            let handler = batch_handler.clone();
+            call_sync(context.blocking, context, move |context| {
+                respond_batch(&*handler, context, query)
+            })
         };
 
         RequestHandler {
             name: f.name,
-            method: actix_web::http::Method::GET,
-            inner: Arc::from(index) as Arc<RawHandler>,
+            method: method.clone(),
+            meta: EndpointMeta::new::<Q, I>(false, false),
+            inner: create_sync_handler(method, handler, |context| context.inner),
+            batch: BatchHandler::Immutable(Arc::new(batch)),
         }
     }
 }
 
 impl<Q, I, F> From<NamedFn<ServiceApiContextMut, Q, I, Result<I>, F>> for RequestHandler
 where
-    F: for<'r> Fn(&'r ServiceApiContextMut, Q) -> Result<I> + 'static + Clone + Send + Sync,
+    F: for<'r> Fn(&'r ServiceApiContextMut, Q) -> Result<I> + 'static + Send + Sync,
     Q: DeserializeOwned + 'static,
     I: Serialize + 'static,
 {
     fn from(f: NamedFn<ServiceApiContextMut, Q, I, Result<I>, F>) -> Self {
-        let handler = f.inner.f;
-        let index = move |request: HttpRequest<ServiceApiContextMut>|
-         -> Box<Future<Item=HttpResponse, Error=actix_web::Error>> {
-            let handler = handler.clone();
-            let context = request.state().clone();
-            request
-                .json()
-                .from_err()
-                .and_then(move |query: Q| {
-                    handler(&context, query)
-                        .map(|value| HttpResponse::Ok().json(value))
-                        .map_err(From::from)
-                })
-                .responder()
+        let handler = Arc::new(f.inner.f);
+        let method = f.method.unwrap_or(Method::POST);
+        let batch_handler = handler.clone();
+        let batch = move |context: ServiceApiContextMut, query: Value| {
+            let handler = batch_handler.clone();
+            call_sync(context.blocking, context, move |context| {
+                respond_batch(&*handler, context, query)
+            })
         };
 
         RequestHandler {
             name: f.name,
-            method: actix_web::http::Method::POST,
-            inner: Arc::from(index) as Arc<RawHandler>,
+            method: method.clone(),
+            meta: EndpointMeta::new::<Q, I>(true, false),
+            inner: create_sync_handler(method, handler, |context| context),
+            batch: BatchHandler::Mutable(Arc::new(batch)),
         }
     }
 }
 
-impl<Q, I, F> From<NamedFn<ServiceApiContext, Q, I, FutureResult<I>, F>> for RequestHandler
+impl<Q, I, F, Fut> From<NamedFn<ServiceApiContext, Q, I, Async<Fut>, F>> for RequestHandler
 where
-    F: for<'r> Fn(&'r ServiceApiContext, Q) -> FutureResult<I> + 'static + Clone + Send + Sync,
+    F: Fn(ServiceApiContext, Q) -> Fut + 'static + Send + Sync,
+    Fut: Future<Output = Result<I>> + 'static,
     Q: DeserializeOwned + 'static,
     I: Serialize + 'static,
 {
-    fn from(f: NamedFn<ServiceApiContext, Q, I, FutureResult<I>, F>) -> Self {
-        let handler = f.inner.f;
-        let index = move |request: HttpRequest<ServiceApiContextMut>|
-         -> Box<Future<Item=HttpResponse, Error=actix_web::Error>> {
-            let context = request.state().clone();
-            let handler = handler.clone();
-            Query::from_request(&request, &())
-                .map(move |query: Query<Q>| query.into_inner())
-                .into_future()
-                .and_then(move |query| handler(&context, query).map_err(From::from))
-                .map(|value| HttpResponse::Ok().json(value))
-                .responder()
-        };
+    fn from(f: NamedFn<ServiceApiContext, Q, I, Async<Fut>, F>) -> Self {
+        let handler = Arc::new(f.inner.f);
+        let method = f.method.unwrap_or(Method::GET);
+        let batch_handler = handler.clone();
+        let index = move |context: ServiceApiContextMut, query: Q| handler(context.inner, query);
 
         RequestHandler {
             name: f.name,
-            method: actix_web::http::Method::GET,
-            inner: Arc::from(index) as Arc<RawHandler>,
+            method: method.clone(),
+            meta: EndpointMeta::new::<Q, I>(false, true),
+            inner: create_raw_handler(method, index),
+            batch: BatchHandler::Immutable(create_batch_handler(
+                move |context: ServiceApiContext, query: Q| batch_handler(context, query),
+            )),
         }
     }
 }
 
-impl<Q, I, F> From<NamedFn<ServiceApiContextMut, Q, I, FutureResult<I>, F>> for RequestHandler
+impl<Q, I, F, Fut> From<NamedFn<ServiceApiContextMut, Q, I, Async<Fut>, F>> for RequestHandler
 where
-    F: for<'r> Fn(&'r ServiceApiContextMut, Q) -> FutureResult<I> + 'static + Clone + Send + Sync,
+    F: Fn(ServiceApiContextMut, Q) -> Fut + 'static + Send + Sync,
+    Fut: Future<Output = Result<I>> + 'static,
     Q: DeserializeOwned + 'static,
     I: Serialize + 'static,
 {
-    fn from(f: NamedFn<ServiceApiContextMut, Q, I, FutureResult<I>, F>) -> Self {
-        let handler = f.inner.f;
-        let index = move |request: HttpRequest<ServiceApiContextMut>|
-         -> Box<Future<Item=HttpResponse, Error=actix_web::Error>> {
-            let handler = handler.clone();
-            let context = request.state().clone();
-            request
-                .json()
-                .from_err()
-                .and_then(move |query: Q| {
-                    handler(&context, query)
-                        .map(|value| HttpResponse::Ok().json(value))
-                        .map_err(From::from)
-                })
-                .responder()
-        };
+    fn from(f: NamedFn<ServiceApiContextMut, Q, I, Async<Fut>, F>) -> Self {
+        let handler = Arc::new(f.inner.f);
+        let method = f.method.unwrap_or(Method::POST);
+        let batch_handler = handler.clone();
 
         RequestHandler {
             name: f.name,
-            method: actix_web::http::Method::POST,
-            inner: Arc::from(index) as Arc<RawHandler>,
+            method: method.clone(),
+            meta: EndpointMeta::new::<Q, I>(true, true),
+            inner: create_raw_handler(method, move |context, query: Q| handler(context, query)),
+            batch: BatchHandler::Mutable(create_batch_handler(
+                move |context: ServiceApiContextMut, query: Q| batch_handler(context, query),
+            )),
         }
     }
 }
@@ -0,0 +1,121 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::actix_backend;
+use crate::error::Error;
+use crate::service::{EndpointHandler, Service, ServiceApiBackend, ServiceApiInitializer};
+use crate::Result;
+
+/// Handlers of the single service.
+#[derive(Clone, Default)]
+pub struct ServiceApiHandlers<H> {
+    pub public: Vec<H>,
+    pub private: Vec<H>,
+}
+
+/// Collects the APIs of the several services for the backend `B`, so each of them
+/// is mounted under its own `services/<name>` prefix.
+pub struct ApiAggregator<B: ServiceApiBackend = actix_backend::BackendBuilder> {
+    services: BTreeMap<String, ServiceApiHandlers<B::Handler>>,
+}
+
+impl<B: ServiceApiBackend> Clone for ApiAggregator<B> {
+    fn clone(&self) -> Self {
+        ApiAggregator {
+            services: self.services.clone(),
+        }
+    }
+}
+
+impl<B: ServiceApiBackend> Default for ApiAggregator<B> {
+    fn default() -> Self {
+        ApiAggregator {
+            services: BTreeMap::new(),
+        }
+    }
+}
+
+/// Checks that the endpoints of the service differ by the name or by the HTTP method,
+/// and that the handlers taking the mutable context are not bound to the safe methods.
+fn check_endpoints<H: EndpointHandler>(service_name: &str, handlers: &[H]) -> Result<()> {
+    let mut endpoints = HashSet::new();
+    for handler in handlers {
+        if handler.meta().mutable && handler.method().is_safe() {
+            return Err(Error::internal(format!(
+                "Endpoint `{}` of the service `{}` takes the mutable context, \
+                 so it cannot be bound to the safe `{}` method",
+                handler.name(),
+                service_name,
+                handler.method()
+            )));
+        }
+        if !endpoints.insert((handler.name(), handler.method())) {
+            return Err(Error::internal(format!(
+                "Service `{}` has duplicate endpoint `{} {}`",
+                service_name,
+                handler.method(),
+                handler.name()
+            )));
+        }
+    }
+    Ok(())
+}
+
+impl<B: ServiceApiBackend + Default> ApiAggregator<B> {
+    pub fn new(services: &[Box<dyn Service<B>>]) -> Result<ApiAggregator<B>> {
+        let mut aggregator = ApiAggregator::default();
+        for service in services {
+            aggregator.add_service(service.as_ref())?;
+        }
+        Ok(aggregator)
+    }
+
+    pub fn add_service(&mut self, service: &dyn Service<B>) -> Result<&mut Self> {
+        let name = service.service_name().to_owned();
+        if self.services.contains_key(&name) {
+            return Err(Error::internal(format!(
+                "Service `{}` is already registered",
+                name
+            )));
+        }
+
+        let mut initializer = ServiceApiInitializer::default();
+        service.initialize_api(&mut initializer);
+
+        let state = initializer.take_state();
+        let with_state = |handlers: Vec<B::Handler>| {
+            handlers
+                .into_iter()
+                .map(|handler| handler.with_state(state.clone()))
+                .collect()
+        };
+        let handlers = ServiceApiHandlers {
+            public: with_state(initializer.public_api_builder.web_backend.finish()),
+            private: with_state(initializer.private_api_builder.web_backend.finish()),
+        };
+        check_endpoints(&name, &handlers.public)?;
+        check_endpoints(&name, &handlers.private)?;
+
+        self.services.insert(name, handlers);
+        Ok(self)
+    }
+
+    /// Returns public handlers along with the prefixes under which they should be mounted.
+    pub fn public_api(&self) -> Vec<(String, Vec<B::Handler>)> {
+        self.services
+            .iter()
+            .map(|(name, handlers)| (Self::service_prefix(name), handlers.public.clone()))
+            .collect()
+    }
+
+    /// Returns private handlers along with the prefixes under which they should be mounted.
+    pub fn private_api(&self) -> Vec<(String, Vec<B::Handler>)> {
+        self.services
+            .iter()
+            .map(|(name, handlers)| (Self::service_prefix(name), handlers.private.clone()))
+            .collect()
+    }
+
+    fn service_prefix(name: &str) -> String {
+        format!("services/{}", name)
+    }
+}
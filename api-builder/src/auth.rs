@@ -0,0 +1,111 @@
+//! Authorization of the requests, which are signed with the service keys of the clients.
+//!
+//! The client signs the canonical message of the request, which binds the signature
+//! to the method, the path and the time of the request, see `signed_message`, and passes
+//! the hex-encoded public key, the signature and the timestamp in the headers. The requests
+//! without the key and the signature headers are anonymous.
+
+use exonum::crypto::{self, PublicKey, SecretKey, Signature};
+use exonum::encoding::serialize::FromHex;
+use http::Method;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::Error;
+use crate::Result;
+
+/// Header with the public key of the client, which has signed the request.
+pub const PUBLIC_KEY_HEADER: &str = "x-public-key";
+
+/// Header with the signature of the request.
+pub const SIGNATURE_HEADER: &str = "x-signature";
+
+/// Header with the time of the request in seconds since the Unix epoch.
+pub const TIMESTAMP_HEADER: &str = "x-timestamp";
+
+/// Maximum difference in seconds between the timestamp of the signed request and the clock
+/// of the server. The signed requests are replayable within this window.
+pub const MAX_TIMESTAMP_SKEW: u64 = 300;
+
+/// Returns the current time in seconds since the Unix epoch, which is used as the timestamp
+/// of the signed requests.
+pub fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
+
+/// Returns the message of the request, which is signed by the client. The message is
+/// the `method\npath\ntimestamp\npayload` string, where the payload is the query string
+/// of the `GET` and `DELETE` requests and the JSON body of the other ones.
+pub fn signed_message(
+    method: &Method,
+    path: &str,
+    timestamp: &str,
+    query: &str,
+    body: &[u8],
+) -> Vec<u8> {
+    let payload = if *method == Method::GET || *method == Method::DELETE {
+        query.as_bytes()
+    } else {
+        body
+    };
+    let mut message = format!("{}\n{}\n{}\n", method, path, timestamp).into_bytes();
+    message.extend_from_slice(payload);
+    message
+}
+
+/// Signs the message of the request, see `signed_message`.
+pub fn sign(message: &[u8], secret_key: &SecretKey) -> Signature {
+    crypto::sign(message, secret_key)
+}
+
+/// Verifies the signature of the request message and returns the public key of the client,
+/// which is absent for the anonymous requests. The signed requests are rejected if their
+/// timestamp differs from the current time by more than `MAX_TIMESTAMP_SKEW` seconds.
+pub fn verify(
+    public_key: Option<&str>,
+    signature: Option<&str>,
+    timestamp: Option<&str>,
+    message: &[u8],
+) -> Result<Option<PublicKey>> {
+    let (public_key, signature) = match (public_key, signature) {
+        (None, None) => return Ok(None),
+        (Some(public_key), Some(signature)) => (public_key, signature),
+        _ => {
+            return Err(Error::unauthorized(format!(
+                "Both `{}` and `{}` headers should be provided",
+                PUBLIC_KEY_HEADER, SIGNATURE_HEADER
+            )))
+        }
+    };
+
+    let timestamp = timestamp
+        .ok_or_else(|| Error::unauthorized(format!("`{}` header is missing", TIMESTAMP_HEADER)))?
+        .parse::<u64>()
+        .map_err(|e| Error::unauthorized(format!("Malformed timestamp: {}", e)))?;
+    let now = self::timestamp();
+    let skew = if now > timestamp {
+        now - timestamp
+    } else {
+        timestamp - now
+    };
+    if skew > MAX_TIMESTAMP_SKEW {
+        return Err(Error::unauthorized(format!(
+            "Timestamp of the request differs from the server time by {} seconds",
+            skew
+        )));
+    }
+
+    let public_key = PublicKey::from_hex(public_key)
+        .map_err(|e| Error::unauthorized(format!("Malformed public key: {}", e)))?;
+    let signature = Signature::from_hex(signature)
+        .map_err(|e| Error::unauthorized(format!("Malformed signature: {}", e)))?;
+    if !crypto::verify(&signature, message, &public_key) {
+        return Err(Error::unauthorized(format!(
+            "Invalid signature of the request by {}",
+            public_key.to_hex()
+        )));
+    }
+    Ok(Some(public_key))
+}
@@ -0,0 +1,158 @@
+//! Batch invocation of the endpoints, which allows to make several requests
+//! in a single round trip.
+
+use http::Method;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::error::{Error, ErrorKind};
+use crate::service::{ServiceApiContext, ServiceApiContextMut, ServiceState};
+use crate::Result;
+
+/// Maximum number of items in the single batch.
+pub const MAX_BATCH_ITEMS: usize = 100;
+
+/// Item of the batch, which is dispatched to the endpoint with the given path, e.g.
+/// `/api/services/explorer/block`, as it is listed by the `/_endpoints`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchItem {
+    pub endpoint: String,
+    /// HTTP method of the endpoint, e.g. `POST`. It may be omitted if the endpoint
+    /// has the single method.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    /// Query of the endpoint, which is the same for any HTTP method of it.
+    #[serde(default)]
+    pub query: Value,
+}
+
+/// Result of the batch item, which is rendered as `{ "ok": .. }` or `{ "error": .. }`.
+/// The messages of the internal errors are logged and are not disclosed to the client.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchResult {
+    Ok(Value),
+    Error(Error),
+}
+
+impl From<Result<Value>> for BatchResult {
+    fn from(result: Result<Value>) -> BatchResult {
+        match result {
+            Ok(value) => BatchResult::Ok(value),
+            Err(error) => {
+                if error.kind == ErrorKind::Internal {
+                    log::error!("Batch item failed: {}", error.message);
+                }
+                BatchResult::Error(error.to_public())
+            }
+        }
+    }
+}
+
+/// Handler of the batch items, which returns the future `F` of the JSON response.
+/// The handlers of the immutable endpoints take the immutable context only.
+pub enum BatchHandler<F> {
+    Immutable(Arc<dyn Fn(ServiceApiContext, Value) -> F + Send + Sync>),
+    Mutable(Arc<dyn Fn(ServiceApiContextMut, Value) -> F + Send + Sync>),
+}
+
+impl<F> Clone for BatchHandler<F> {
+    fn clone(&self) -> Self {
+        match *self {
+            BatchHandler::Immutable(ref handler) => BatchHandler::Immutable(handler.clone()),
+            BatchHandler::Mutable(ref handler) => BatchHandler::Mutable(handler.clone()),
+        }
+    }
+}
+
+impl<F: 'static> BatchHandler<F> {
+    /// Makes the state of the service available to the handler through the context.
+    pub fn with_state(self, state: ServiceState) -> BatchHandler<F> {
+        match self {
+            BatchHandler::Immutable(handler) => {
+                BatchHandler::Immutable(Arc::new(move |mut context: ServiceApiContext, query| {
+                    context.state = state.clone();
+                    handler(context, query)
+                }))
+            }
+            BatchHandler::Mutable(handler) => {
+                BatchHandler::Mutable(Arc::new(move |mut context: ServiceApiContextMut, query| {
+                    context.inner.state = state.clone();
+                    handler(context, query)
+                }))
+            }
+        }
+    }
+}
+
+/// Handlers of the batch items by the methods and the paths of the endpoints.
+pub type BatchHandlers<F> = HashMap<(Method, String), BatchHandler<F>>;
+
+/// Finds the handler of the item by its method or, if the method is omitted, by the path only.
+fn find_handler<'a, F>(
+    handlers: &'a BatchHandlers<F>,
+    item: &BatchItem,
+) -> Result<&'a BatchHandler<F>> {
+    let not_found = || Error::not_found(format!("Endpoint `{}` does not exist", item.endpoint));
+    match item.method {
+        Some(ref method) => {
+            let method = Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+                .map_err(|_| Error::bad_request(format!("Invalid HTTP method `{}`", method)))?;
+            handlers
+                .get(&(method, item.endpoint.clone()))
+                .ok_or_else(not_found)
+        }
+        None => {
+            let mut found = handlers
+                .iter()
+                .filter(|((_, path), _)| *path == item.endpoint)
+                .map(|(_, handler)| handler);
+            match (found.next(), found.next()) {
+                (Some(handler), None) => Ok(handler),
+                (Some(_), Some(_)) => Err(Error::bad_request(format!(
+                    "Endpoint `{}` has several methods, the `method` should be specified",
+                    item.endpoint
+                ))),
+                (None, _) => Err(not_found()),
+            }
+        }
+    }
+}
+
+/// Dispatches the items to the handlers one by one, so the items of the batch may rely
+/// on the changes made by the previous ones. The mutable context is used only by the items
+/// dispatched to the mutable endpoints.
+///
+/// Fails if the batch contains more than `MAX_BATCH_ITEMS` items.
+pub async fn dispatch<F>(
+    context: ServiceApiContextMut,
+    handlers: &BatchHandlers<F>,
+    items: Vec<BatchItem>,
+) -> Result<Vec<BatchResult>>
+where
+    F: Future<Output = Result<Value>>,
+{
+    if items.len() > MAX_BATCH_ITEMS {
+        return Err(Error::bad_request(format!(
+            "Max batch items count exceeded ({})",
+            MAX_BATCH_ITEMS
+        )));
+    }
+
+    let mut results = Vec::with_capacity(items.len());
+    for item in items {
+        let result = match find_handler(handlers, &item) {
+            Ok(BatchHandler::Immutable(handler)) => {
+                handler(context.inner.clone(), item.query).await
+            }
+            Ok(BatchHandler::Mutable(handler)) => handler(context.clone(), item.query).await,
+            Err(error) => Err(error),
+        };
+        results.push(result.into());
+    }
+    Ok(results)
+}
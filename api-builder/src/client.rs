@@ -0,0 +1,243 @@
+//! Typed client of the service APIs, which are declared with the `service_api!` macro.
+
+use exonum::crypto::{PublicKey, SecretKey};
+use http::{Method, StatusCode};
+use hyper::client::HttpConnector;
+use hyper::header::CONTENT_TYPE;
+use hyper::{Body, Client, Request};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use std::fmt;
+
+use crate::auth::{self, PUBLIC_KEY_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use crate::error::Error;
+use crate::service::{ServiceApiContext, ServiceApiContextMut};
+use crate::Result;
+
+/// Context of the endpoint handler, which defines the default HTTP method of the endpoint.
+pub trait EndpointContext {
+    const METHOD: Method;
+}
+
+impl EndpointContext for ServiceApiContext {
+    const METHOD: Method = Method::GET;
+}
+
+impl EndpointContext for ServiceApiContextMut {
+    const METHOD: Method = Method::POST;
+}
+
+/// HTTP client of the node, which makes the requests to the endpoints of the services.
+#[derive(Clone)]
+pub struct ApiClient {
+    http: Client<HttpConnector>,
+    base_url: String,
+    /// Keys of the client, which signs the requests with them, see `auth`.
+    keys: Option<(PublicKey, SecretKey)>,
+}
+
+impl ApiClient {
+    /// Creates the client of the node with the given base URL, e.g. `http://127.0.0.1:8080`.
+    pub fn new<S: Into<String>>(base_url: S) -> ApiClient {
+        ApiClient {
+            http: Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_owned(),
+            keys: None,
+        }
+    }
+
+    /// Signs the requests with the given keys, so the handlers get the public key
+    /// of the client with `ServiceApiContext::caller`.
+    pub fn with_keys(mut self, public_key: PublicKey, secret_key: SecretKey) -> ApiClient {
+        self.keys = Some((public_key, secret_key));
+        self
+    }
+
+    /// Calls the endpoint mounted under the `api/<prefix>` path with the default method
+    /// of its context.
+    pub async fn endpoint<S, Q, I>(&self, prefix: &str, name: &str, query: &Q) -> Result<I>
+    where
+        S: EndpointContext,
+        Q: Serialize,
+        I: DeserializeOwned,
+    {
+        let path = format!("/api/{}/{}", prefix, name);
+        self.request(S::METHOD, &path, query).await
+    }
+
+    /// Makes the request to the given path. The query is sent in the query string
+    /// for the `GET` and `DELETE` requests and as the JSON body for the other ones.
+    /// The request is signed if the client has the keys.
+    pub async fn request<Q, I>(&self, method: Method, path: &str, query: &Q) -> Result<I>
+    where
+        Q: Serialize,
+        I: DeserializeOwned,
+    {
+        let query = serde_json::to_value(query).map_err(Error::bad_request)?;
+        let mut builder = Request::builder().method(method.clone());
+        let (query, body) = if method == Method::GET || method == Method::DELETE {
+            let query = match query {
+                Value::Null => String::new(),
+                query => serde_urlencoded::to_string(query).map_err(Error::bad_request)?,
+            };
+            (query, Vec::new())
+        } else {
+            builder = builder.header(CONTENT_TYPE, "application/json");
+            let body = serde_json::to_vec(&query).map_err(Error::bad_request)?;
+            (String::new(), body)
+        };
+        let uri = if query.is_empty() {
+            format!("{}{}", self.base_url, path)
+        } else {
+            format!("{}{}?{}", self.base_url, path, query)
+        };
+        if let Some((ref public_key, ref secret_key)) = self.keys {
+            let timestamp = auth::timestamp().to_string();
+            let message = auth::signed_message(&method, path, &timestamp, &query, &body);
+            builder = builder
+                .header(PUBLIC_KEY_HEADER, public_key.to_hex())
+                .header(SIGNATURE_HEADER, auth::sign(&message, secret_key).to_hex())
+                .header(TIMESTAMP_HEADER, timestamp);
+        }
+        let request = builder
+            .uri(uri)
+            .body(Body::from(body))
+            .map_err(Error::bad_request)?;
+
+        let response = self.http.request(request).await.map_err(Error::internal)?;
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .map_err(Error::internal)?;
+        if status.is_success() {
+            serde_json::from_slice(&body).map_err(Error::internal)
+        } else {
+            // The errors of the endpoints are rendered as JSON, unlike the ones of the server,
+            // e.g. if the endpoint is not mounted.
+            Err(serde_json::from_slice(&body).unwrap_or_else(|_| {
+                let message = format!("Unexpected response status {}", status);
+                match status {
+                    StatusCode::BAD_REQUEST => Error::bad_request(message),
+                    StatusCode::NOT_FOUND => Error::not_found(message),
+                    _ => Error::internal(message),
+                }
+            }))
+        }
+    }
+}
+
+/// The secret key is not printed.
+impl fmt::Debug for ApiClient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ApiClient")
+            .field("base_url", &self.base_url)
+            .field(
+                "public_key",
+                &self.keys.as_ref().map(|(public_key, _)| public_key),
+            )
+            .finish()
+    }
+}
+
+/// Declares the API of the service once for both the server and the client.
+///
+/// The macro defines the trait, which should be implemented for the given context type,
+/// with the `wire` method registering the endpoints named after the trait methods,
+/// and the typed client calling these endpoints.
+///
+/// Only the synchronous endpoints with the default method of the context, i.e. `GET`
+/// for `ServiceApiContext` and `POST` for `ServiceApiContextMut`, may be declared.
+/// The asynchronous endpoints and the ones with the other methods are registered with
+/// `endpoint_async` and `endpoint_with` next to the `wire` call.
+///
+/// ```ignore
+/// service_api! {
+///     /// Public API of the wallets service.
+///     pub trait WalletsApi for ServiceApiContext {
+///         client WalletsClient;
+///
+///         fn balance(&self, query: BalanceQuery) -> u64;
+///     }
+/// }
+///
+/// // Server side, within `Service::initialize_api`.
+/// <ServiceApiContext as WalletsApi>::wire(initializer.public_api());
+/// // Client side.
+/// let client = WalletsClient::new(ApiClient::new("http://127.0.0.1:8080"), "wallets");
+/// let balance = client.balance(&BalanceQuery { key }).await?;
+/// ```
+#[macro_export]
+macro_rules! service_api {
+    (
+        $(#[$attr:meta])*
+        $vis:vis trait $name:ident for $context:ty {
+            client $client:ident;
+
+            $(
+                $(#[$method_attr:meta])*
+                fn $method:ident(&self, $query_name:ident: $query:ty) -> $item:ty;
+            )*
+        }
+    ) => {
+        $(#[$attr])*
+        $vis trait $name {
+            $(
+                $(#[$method_attr])*
+                fn $method(&self, $query_name: $query) -> $crate::Result<$item>;
+            )*
+
+            /// Registers the endpoints of the API, which are named after the trait methods.
+            fn wire<B>(builder: &mut $crate::service::ServiceApiBuilder<B>)
+            where
+                Self: Sized,
+                B: $crate::service::ServiceApiBackend,
+                $(
+                    B::Handler: From<$crate::NamedFn<
+                        $context,
+                        $query,
+                        $item,
+                        $crate::Result<$item>,
+                        fn(&$context, $query) -> $crate::Result<$item>,
+                    >>,
+                )*
+            {
+                $(
+                    builder.endpoint(
+                        stringify!($method),
+                        <$context as $name>::$method
+                            as fn(&$context, $query) -> $crate::Result<$item>,
+                    );
+                )*
+            }
+        }
+
+        /// Typed client of the API.
+        #[derive(Debug, Clone)]
+        $vis struct $client {
+            client: $crate::client::ApiClient,
+            prefix: String,
+        }
+
+        impl $client {
+            /// Creates the client of the service API, which is mounted under
+            /// the `services/<service_name>` prefix by the `ApiAggregator`.
+            $vis fn new(client: $crate::client::ApiClient, service_name: &str) -> Self {
+                $client {
+                    client,
+                    prefix: format!("services/{}", service_name),
+                }
+            }
+
+            $(
+                $(#[$method_attr])*
+                $vis async fn $method(&self, $query_name: &$query) -> $crate::Result<$item> {
+                    self.client
+                        .endpoint::<$context, _, _>(&self.prefix, stringify!($method), $query_name)
+                        .await
+                }
+            )*
+        }
+    };
+}
@@ -0,0 +1,258 @@
+//! Configuration of the node binary, which is read from the TOML file
+//! and the command line arguments. Several local nodes may be run with the separate
+//! configuration files, for example:
+//!
+//! ```toml
+//! public_api_address = "127.0.0.1:8090"
+//! private_api_address = "127.0.0.1:8091"
+//! database_path = "/tmp/api-builder/node-1"
+//! key_file = "/tmp/api-builder/node-1.keys.toml"
+//! log_level = "info"
+//! blocking_threads = 4
+//! ```
+
+use clap::{App, Arg};
+use exonum::crypto::{self, PublicKey, SecretKey};
+use failure::{bail, Error};
+use serde_derive::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use std::ffi::OsString;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+/// Configuration of the node. Any field may be omitted in the TOML file, the command line
+/// arguments take precedence over the file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NodeConfig {
+    pub public_api_address: String,
+    pub private_api_address: String,
+    /// Path of the RocksDB database, the data is kept in memory if it is absent.
+    pub database_path: Option<PathBuf>,
+    /// File with the service keys, which is created if it does not exist.
+    /// The keys are generated for each run if the file is absent.
+    pub key_file: Option<PathBuf>,
+    /// Filter of the log records in the `RUST_LOG` format, e.g. `api_builder=debug`.
+    pub log_level: Option<String>,
+    /// Maximum number of the blocking threads of each server worker, which run
+    /// the synchronous handlers, one per each available CPU by default.
+    pub blocking_threads: Option<usize>,
+}
+
+impl Default for NodeConfig {
+    fn default() -> NodeConfig {
+        NodeConfig {
+            public_api_address: "127.0.0.1:8080".to_owned(),
+            private_api_address: "127.0.0.1:8081".to_owned(),
+            database_path: None,
+            key_file: None,
+            log_level: None,
+            blocking_threads: None,
+        }
+    }
+}
+
+impl NodeConfig {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<NodeConfig, Error> {
+        let contents = fs::read_to_string(path)?;
+        Ok(toml::from_str(&contents)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Parses the command line arguments, the first one is the name of the binary.
+    /// The values missing in the arguments are taken from the `--config` file, if any.
+    pub fn from_args<I, T>(args: I) -> Result<NodeConfig, Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let matches = app().get_matches_from_safe(args)?;
+        let mut config = match matches.value_of_os("config") {
+            Some(path) => NodeConfig::load(path)?,
+            None => NodeConfig::default(),
+        };
+
+        if let Some(address) = matches.value_of("public-api-address") {
+            config.public_api_address = address.to_owned();
+        }
+        if let Some(address) = matches.value_of("private-api-address") {
+            config.private_api_address = address.to_owned();
+        }
+        if let Some(path) = matches.value_of_os("database-path") {
+            config.database_path = Some(path.into());
+        }
+        if let Some(path) = matches.value_of_os("key-file") {
+            config.key_file = Some(path.into());
+        }
+        if let Some(level) = matches.value_of("log-level") {
+            config.log_level = Some(level.to_owned());
+        }
+        if let Some(threads) = matches.value_of("blocking-threads") {
+            config.blocking_threads = Some(threads.parse()?);
+        }
+        Ok(config)
+    }
+
+    /// Returns the keys from the key file or the generated ones if there is no file.
+    pub fn service_keys(&self) -> Result<ServiceKeys, Error> {
+        match self.key_file {
+            Some(ref path) => ServiceKeys::load_or_generate(path),
+            None => Ok(ServiceKeys::generate()),
+        }
+    }
+
+    /// Returns the configured number of the blocking threads or the number
+    /// of the available CPUs.
+    pub fn max_blocking_threads(&self) -> Result<usize, Error> {
+        match self.blocking_threads {
+            Some(0) => bail!("Number of the blocking threads should be positive"),
+            Some(threads) => Ok(threads),
+            None => Ok(thread::available_parallelism().map_or(1, |threads| threads.get())),
+        }
+    }
+}
+
+fn app() -> App<'static, 'static> {
+    App::new("api-builder")
+        .about("Serves the APIs of the example services")
+        .arg(
+            Arg::with_name("config")
+                .short("c")
+                .long("config")
+                .value_name("FILE")
+                .help("TOML configuration file")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("public-api-address")
+                .long("public-api-address")
+                .value_name("ADDRESS")
+                .help("Listen address of the public API, 127.0.0.1:8080 by default")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("private-api-address")
+                .long("private-api-address")
+                .value_name("ADDRESS")
+                .help("Listen address of the private API, 127.0.0.1:8081 by default")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("database-path")
+                .short("d")
+                .long("database-path")
+                .value_name("PATH")
+                .help("Path of the RocksDB database, the data is kept in memory by default")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("key-file")
+                .short("k")
+                .long("key-file")
+                .value_name("FILE")
+                .help("File with the service keys, which is generated if it does not exist")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("log-level")
+                .short("l")
+                .long("log-level")
+                .value_name("FILTER")
+                .help("Filter of the log records in the RUST_LOG format")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("blocking-threads")
+                .long("blocking-threads")
+                .value_name("COUNT")
+                .help("Maximum number of the threads per worker running the synchronous handlers")
+                .takes_value(true),
+        )
+}
+
+/// Service keys of the node, which are stored in the TOML key file.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceKeys {
+    pub public_key: PublicKey,
+    pub secret_key: SecretKey,
+}
+
+impl ServiceKeys {
+    pub fn generate() -> ServiceKeys {
+        let (public_key, secret_key) = crypto::gen_keypair();
+        ServiceKeys {
+            public_key,
+            secret_key,
+        }
+    }
+
+    /// Loads the keys from the file or generates the new ones and saves them to the file,
+    /// if it does not exist. The new file is readable by the owner only on unix.
+    ///
+    /// The keys are written to the temporary file in the same directory, which is then
+    /// hard linked to the given path, so the concurrent readers never see the partial file,
+    /// and the keys of the concurrent starter, which has linked its file first, are loaded.
+    pub fn load_or_generate<P: AsRef<Path>>(path: P) -> Result<ServiceKeys, Error> {
+        let path = path.as_ref();
+        if !path.exists() {
+            let keys = ServiceKeys::generate();
+            let temp_path = temp_path(path);
+            let linked = write_private(&temp_path, toml::to_string(&keys)?.as_bytes())
+                .and_then(|()| fs::hard_link(&temp_path, path));
+            // The temporary file is removed even if it has not been written.
+            let _ = fs::remove_file(&temp_path);
+            match linked {
+                Ok(()) => return Ok(keys),
+                // The file is loaded below, since it was created concurrently.
+                Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let keys: ServiceKeys = toml::from_str(&fs::read_to_string(path)?)?;
+        let signature = crypto::sign(&[], &keys.secret_key);
+        if !crypto::verify(&signature, &[], &keys.public_key) {
+            bail!(
+                "Public key does not match the secret key in {}",
+                path.display()
+            );
+        }
+        Ok(keys)
+    }
+}
+
+/// Returns the unique path of the temporary file next to the given one.
+fn temp_path(path: &Path) -> PathBuf {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}.{}.tmp", file_name, Uuid::new_v4()))
+}
+
+/// Creates the new file, which is readable by the owner only on unix, with the given contents.
+fn write_private(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+/// The secret key is not printed.
+impl fmt::Debug for ServiceKeys {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ServiceKeys")
+            .field("public_key", &self.public_key)
+            .finish()
+    }
+}
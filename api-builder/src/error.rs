@@ -1 +1,103 @@
-pub use failure::Error;
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use serde_derive::{Deserialize, Serialize};
+
+use std::error::Error as StdError;
+use std::fmt;
+
+/// Message of the internal errors, which is sent to the clients instead of the details
+/// of the failure.
+pub const INTERNAL_ERROR_MESSAGE: &str = "Internal server error";
+
+/// Kind of the API error, which defines the response status code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// Request is malformed, e.g. the query or the body cannot be parsed.
+    BadRequest,
+    /// Signature of the request is invalid or the endpoint requires the signed request.
+    Unauthorized,
+    /// Requested entity does not exist.
+    NotFound,
+    /// Request is correct, but the node has failed to handle it.
+    Internal,
+}
+
+impl ErrorKind {
+    pub fn status_code(self) -> StatusCode {
+        match self {
+            ErrorKind::BadRequest => StatusCode::BAD_REQUEST,
+            ErrorKind::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorKind::NotFound => StatusCode::NOT_FOUND,
+            ErrorKind::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// API error, which is rendered as JSON `{ "code": .., "message": .. }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Error {
+    #[serde(rename = "code")]
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl Error {
+    pub fn new<M: ToString>(kind: ErrorKind, message: M) -> Error {
+        Error {
+            kind,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn bad_request<M: ToString>(message: M) -> Error {
+        Error::new(ErrorKind::BadRequest, message)
+    }
+
+    pub fn unauthorized<M: ToString>(message: M) -> Error {
+        Error::new(ErrorKind::Unauthorized, message)
+    }
+
+    pub fn not_found<M: ToString>(message: M) -> Error {
+        Error::new(ErrorKind::NotFound, message)
+    }
+
+    pub fn internal<M: ToString>(message: M) -> Error {
+        Error::new(ErrorKind::Internal, message)
+    }
+
+    /// Returns the error, which is sent to the client. The messages of the internal errors,
+    /// e.g. the storage failures, are replaced with `INTERNAL_ERROR_MESSAGE`, so only
+    /// the logs disclose them.
+    pub fn to_public(&self) -> Error {
+        match self.kind {
+            ErrorKind::Internal => Error::internal(INTERNAL_ERROR_MESSAGE),
+            _ => self.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl StdError for Error {}
+
+/// Errors without the explicit kind are considered internal.
+impl From<failure::Error> for Error {
+    fn from(e: failure::Error) -> Error {
+        Error::internal(e)
+    }
+}
+
+impl ResponseError for Error {
+    fn status_code(&self) -> StatusCode {
+        self.kind.status_code()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(self.to_public())
+    }
+}
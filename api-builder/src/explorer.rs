@@ -0,0 +1,163 @@
+//! Built-in service, which allows to explore the blocks and transactions of the blockchain.
+
+use exonum::blockchain::{Block, Schema};
+use exonum::crypto::Hash;
+use exonum::explorer::{BlockchainExplorer, TransactionInfo};
+use exonum::helpers::Height;
+use exonum::messages::Precommit;
+use serde_derive::{Deserialize, Serialize};
+
+use std::ops::Range;
+
+use crate::error::Error;
+use crate::service::{Service, ServiceApiContext, ServiceApiInitializer};
+use crate::Result;
+
+/// Maximum number of blocks returned by the single `blocks` request.
+pub const MAX_BLOCKS_PER_REQUEST: usize = 1000;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BlockQuery {
+    pub height: Height,
+}
+
+/// Query of the blocks range, which ends with the `latest` block or the last committed one.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BlocksQuery {
+    /// Maximum number of blocks in the response, from 1 to `MAX_BLOCKS_PER_REQUEST`.
+    pub count: usize,
+    pub latest: Option<Height>,
+    #[serde(default)]
+    pub skip_empty_blocks: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TransactionQuery {
+    pub hash: Hash,
+}
+
+/// Block header along with the precommits and the hashes of the block transactions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockInfo {
+    pub block: Block,
+    pub precommits: Vec<Precommit>,
+    pub txs: Vec<Hash>,
+}
+
+/// Blocks in the descending order of heights along with the range which they cover.
+/// The next page of blocks ends with the block preceding the `range.start` one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlocksRange {
+    pub range: Range<Height>,
+    pub blocks: Vec<Block>,
+}
+
+impl ServiceApiContext {
+    pub fn explorer(&self) -> BlockchainExplorer {
+        BlockchainExplorer::new(&self.blockchain)
+    }
+}
+
+/// Returns the height of the latest committed block, the explorer methods relying on it
+/// panic until the genesis block is created.
+fn latest_height(context: &ServiceApiContext) -> Result<Height> {
+    if Schema::new(&context.blockchain.snapshot())
+        .block_hashes_by_height()
+        .is_empty()
+    {
+        return Err(Error::not_found("Genesis block is not created yet"));
+    }
+    Ok(context.explorer().height())
+}
+
+/// Returns the height of the latest committed block.
+pub fn height(context: &ServiceApiContext, _: ()) -> Result<Height> {
+    latest_height(context)
+}
+
+pub fn block(context: &ServiceApiContext, query: BlockQuery) -> Result<BlockInfo> {
+    latest_height(context)?;
+    let explorer = context.explorer();
+    let block = explorer
+        .block(query.height)
+        .ok_or_else(|| Error::not_found(format!("Block {} does not exist", query.height)))?;
+    let precommits = block.precommits().to_vec();
+    let txs = block.transaction_hashes().to_vec();
+    Ok(BlockInfo {
+        block: block.into_header(),
+        precommits,
+        txs,
+    })
+}
+
+pub fn blocks(context: &ServiceApiContext, query: BlocksQuery) -> Result<BlocksRange> {
+    if query.count == 0 {
+        return Err(Error::bad_request("Block count should be positive"));
+    }
+    if query.count > MAX_BLOCKS_PER_REQUEST {
+        return Err(Error::bad_request(format!(
+            "Max block count per request exceeded ({})",
+            MAX_BLOCKS_PER_REQUEST
+        )));
+    }
+
+    let height = latest_height(context)?;
+    let upper = match query.latest {
+        Some(latest) if latest > height => {
+            return Err(Error::not_found(format!("Block {} does not exist", latest)))
+        }
+        Some(latest) => latest,
+        None => height,
+    };
+    let explorer = context.explorer();
+    let blocks = explorer
+        .blocks(..upper.next())
+        .rev()
+        .filter(|block| !query.skip_empty_blocks || !block.is_empty())
+        .take(query.count)
+        .map(|block| block.into_header())
+        .collect::<Vec<_>>();
+
+    // The range starts from the genesis block if there are no more blocks to return.
+    let start = if blocks.len() < query.count {
+        Height::zero()
+    } else {
+        blocks.last().map_or(Height::zero(), |block| block.height())
+    };
+    Ok(BlocksRange {
+        range: start..upper.next(),
+        blocks,
+    })
+}
+
+/// Returns the transaction with its execution status, if it is committed, or the content
+/// of the transaction from the pool.
+pub fn transaction(
+    context: &ServiceApiContext,
+    query: TransactionQuery,
+) -> Result<TransactionInfo> {
+    let hash = query.hash;
+    context
+        .explorer()
+        .transaction(&hash)
+        .ok_or_else(|| Error::not_found(format!("Transaction {} is unknown", hash.to_hex())))
+}
+
+/// Service with the explorer endpoints, which are mounted under the `services/explorer` prefix.
+#[derive(Debug, Default)]
+pub struct ExplorerService;
+
+impl Service for ExplorerService {
+    fn service_name(&self) -> &str {
+        "explorer"
+    }
+
+    fn initialize_api(&self, initializer: &mut ServiceApiInitializer) {
+        initializer
+            .public_api()
+            .endpoint("height", height)
+            .endpoint("block", block)
+            .endpoint("blocks", blocks)
+            .endpoint("transaction", transaction);
+    }
+}
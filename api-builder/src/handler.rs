@@ -0,0 +1,298 @@
+//! Handling of the endpoint requests, which is shared by the backends: the access log,
+//! the authorization, the query extraction and the batch dispatch. The backends only adapt
+//! their requests and responses to it.
+
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use http::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::access_log::{self, AccessLog, REQUEST_ID_HEADER};
+use crate::auth::{self, PUBLIC_KEY_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+use crate::batch::{self, BatchHandler, BatchHandlers, BatchItem};
+use crate::error::Error;
+use crate::service::{
+    EndpointHandler, EndpointInfo, EndpointMeta, ServiceApiContextMut, ServiceState,
+};
+use crate::Result;
+
+/// Raw handler takes the query string and the body of the request and returns the future `F`
+/// of the JSON response body.
+pub type RawHandler<F> = dyn Fn(ServiceApiContextMut, &str, &[u8]) -> F + 'static + Send + Sync;
+
+/// Handler of the endpoint, which is mounted by the backend. The backend boxes the futures
+/// of the endpoint requests into `F` and the futures of the batch items into `B`.
+pub struct RequestHandler<F, B> {
+    pub name: &'static str,
+    pub method: Method,
+    pub meta: EndpointMeta,
+    pub inner: Arc<RawHandler<F>>,
+    /// Handler of the items of the `/batch` requests.
+    pub batch: BatchHandler<B>,
+}
+
+impl<F, B> Clone for RequestHandler<F, B> {
+    fn clone(&self) -> Self {
+        RequestHandler {
+            name: self.name,
+            method: self.method.clone(),
+            meta: self.meta,
+            inner: self.inner.clone(),
+            batch: self.batch.clone(),
+        }
+    }
+}
+
+impl<F: 'static, B: 'static> RequestHandler<F, B> {
+    /// Describes the endpoint mounted under the given `api/<prefix>` scope.
+    pub fn info(&self, prefix: &str) -> EndpointInfo {
+        EndpointInfo::new(prefix, self.name, &self.method, &self.meta)
+    }
+}
+
+impl<F: 'static, B: 'static> EndpointHandler for RequestHandler<F, B> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn method(&self) -> &Method {
+        &self.method
+    }
+
+    fn meta(&self) -> &EndpointMeta {
+        &self.meta
+    }
+
+    fn with_state(self, state: ServiceState) -> Self {
+        let batch = self.batch.with_state(state.clone());
+        let inner = self.inner;
+        let index = move |mut context: ServiceApiContextMut, query: &str, body: &[u8]| {
+            context.inner.state = state.clone();
+            inner(context, query, body)
+        };
+        RequestHandler {
+            inner: Arc::new(index),
+            batch,
+            ..self
+        }
+    }
+}
+
+/// Lists the endpoints of the handlers mounted under the `api/<prefix>` scopes.
+pub fn endpoints<F: 'static, B: 'static>(
+    apis: &[(String, Vec<RequestHandler<F, B>>)],
+) -> Vec<EndpointInfo> {
+    apis.iter()
+        .flat_map(|(prefix, handlers)| handlers.iter().map(move |handler| handler.info(prefix)))
+        .collect()
+}
+
+/// Collects the handlers of the batch items by the methods and the paths of the endpoints
+/// mounted under the `api/<prefix>` scopes.
+pub fn batch_handlers<F: 'static, B: 'static>(
+    apis: &[(String, Vec<RequestHandler<F, B>>)],
+) -> BatchHandlers<B> {
+    let mut batch_handlers = BatchHandlers::new();
+    for (prefix, handlers) in apis {
+        for handler in handlers {
+            let key = (handler.method.clone(), handler.info(prefix).path);
+            batch_handlers.insert(key, handler.batch.clone());
+        }
+    }
+    batch_handlers
+}
+
+/// Headers of the request in the representation of the backend.
+pub trait Headers {
+    /// Returns the value of the header, which is empty if it is not the visible ASCII.
+    fn get_str(&self, name: &str) -> Option<&str>;
+}
+
+impl Headers for HeaderMap {
+    fn get_str(&self, name: &str) -> Option<&str> {
+        self.get(name)
+            .map(|value| value.to_str().unwrap_or_default())
+    }
+}
+
+/// Request to the endpoint, which is adapted by the backend.
+pub struct Request<'a> {
+    pub method: &'a Method,
+    pub path: &'a str,
+    /// Raw query string, which is empty if the request has no query.
+    pub query: &'a str,
+    pub headers: &'a dyn Headers,
+    pub body: &'a [u8],
+}
+
+/// Response of the backend, which renders the results of the handlers.
+pub trait Response: Sized {
+    /// Creates the successful response with the given JSON body.
+    fn json(body: Vec<u8>) -> Self;
+
+    /// Creates the JSON response of the error, which does not disclose the internal errors,
+    /// see `Error::to_public`.
+    fn error(error: &Error) -> Self;
+
+    fn status(&self) -> StatusCode;
+
+    fn insert_header(&mut self, name: HeaderName, value: HeaderValue);
+}
+
+/// Starts the access log record of the request to the endpoint with the given name
+/// and passes the ID of the request to the handler through the context.
+fn start_request(
+    endpoint: &str,
+    request: &Request,
+    context: &mut ServiceApiContextMut,
+) -> AccessLog {
+    let request_id = access_log::request_id(request.headers.get_str(REQUEST_ID_HEADER));
+    context.inner.request_id = Some(request_id.clone());
+    AccessLog::start(endpoint, request.path, request.method.clone(), request_id)
+}
+
+/// Verifies the signature of the request, if any, and passes the public key of the client
+/// to the handler through the context.
+fn authorize(request: &Request, context: &mut ServiceApiContextMut) -> Result<()> {
+    let timestamp = request.headers.get_str(TIMESTAMP_HEADER);
+    let message = auth::signed_message(
+        request.method,
+        request.path,
+        timestamp.unwrap_or_default(),
+        request.query,
+        request.body,
+    );
+    context.inner.caller = auth::verify(
+        request.headers.get_str(PUBLIC_KEY_HEADER),
+        request.headers.get_str(SIGNATURE_HEADER),
+        timestamp,
+        &message,
+    )?;
+    Ok(())
+}
+
+/// Renders the response of the handler, which carries the ID of the request,
+/// and finishes the access log record.
+async fn finish_request<R, Fut>(log: AccessLog, response: Fut) -> R
+where
+    R: Response,
+    Fut: Future<Output = Result<Vec<u8>>>,
+{
+    let request_id = log.request_id().to_owned();
+    let mut response = match response.await {
+        Ok(body) => {
+            let response = R::json(body);
+            log.finish(response.status(), None);
+            response
+        }
+        Err(error) => {
+            log.finish(error.kind.status_code(), Some(&error));
+            R::error(&error)
+        }
+    };
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.insert_header(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+    response
+}
+
+/// Handles the request to the endpoint with the given name. The request is logged
+/// to the access log, the signed request is verified and the response carries the ID
+/// of the request.
+pub fn handle<R, F>(
+    endpoint: &str,
+    request: &Request,
+    mut context: ServiceApiContextMut,
+    handler: &RawHandler<F>,
+) -> impl Future<Output = R>
+where
+    R: Response,
+    F: Future<Output = Result<Vec<u8>>>,
+{
+    let log = start_request(endpoint, request, &mut context);
+    let response =
+        authorize(request, &mut context).map(|()| handler(context, request.query, request.body));
+    finish_request(log, async move { response?.await })
+}
+
+/// Handles the `/batch` request, which invokes the endpoints with the given handlers,
+/// see `batch::dispatch`. The request is logged and authorized as the endpoint requests.
+pub fn handle_batch<R, B>(
+    request: &Request,
+    mut context: ServiceApiContextMut,
+    handlers: Arc<BatchHandlers<B>>,
+) -> impl Future<Output = R>
+where
+    R: Response,
+    B: Future<Output = Result<Value>>,
+{
+    let log = start_request("batch", request, &mut context);
+    let authorized = authorize(request, &mut context);
+    let items = serde_json::from_slice::<Vec<BatchItem>>(request.body);
+    finish_request(log, async move {
+        authorized?;
+        let items = items.map_err(Error::bad_request)?;
+        let results = batch::dispatch(context, &handlers, items).await?;
+        serde_json::to_vec(&results).map_err(Error::internal)
+    })
+}
+
+/// Extracts the endpoint query from the query string of the `GET` and `DELETE` requests
+/// and from the JSON body of the other ones.
+pub fn extract_query<Q>(method: &Method, query: &str, body: &[u8]) -> Result<Q>
+where
+    Q: DeserializeOwned,
+{
+    if *method == Method::GET || *method == Method::DELETE {
+        serde_urlencoded::from_str(query).map_err(Error::bad_request)
+    } else {
+        serde_json::from_slice(body).map_err(Error::bad_request)
+    }
+}
+
+/// Calls the handler with the query extracted from the request, the returned future
+/// resolves to the JSON of the handler result.
+pub fn call_handler<S, Q, I, H, Fut>(
+    handler: &H,
+    context: S,
+    method: &Method,
+    query: &str,
+    body: &[u8],
+) -> impl Future<Output = Result<Vec<u8>>>
+where
+    Q: DeserializeOwned,
+    I: Serialize,
+    H: Fn(S, Q) -> Fut,
+    Fut: Future<Output = Result<I>>,
+{
+    let response = extract_query(method, query, body).map(|query| handler(context, query));
+    async move {
+        let value = response?.await?;
+        serde_json::to_vec(&value).map_err(Error::internal)
+    }
+}
+
+/// Calls the handler with the query of the batch item, which is taken as JSON.
+pub fn call_batch_handler<S, Q, I, H, Fut>(
+    handler: &H,
+    context: S,
+    query: Value,
+) -> impl Future<Output = Result<Value>>
+where
+    Q: DeserializeOwned,
+    I: Serialize,
+    H: Fn(S, Q) -> Fut,
+    Fut: Future<Output = Result<I>>,
+{
+    let response = serde_json::from_value(query)
+        .map_err(Error::bad_request)
+        .map(|query| handler(context, query));
+    async move {
+        let value = response?.await?;
+        serde_json::to_value(value).map_err(Error::internal)
+    }
+}
@@ -1,46 +1,79 @@
-extern crate actix_web;
-extern crate exonum;
-extern crate failure;
-extern crate futures;
-extern crate serde;
-extern crate serde_json;
+use std::future::Future;
+use std::marker::PhantomData;
 
-use futures::Future;
-
-use service::{ServiceApiContext, ServiceApiContextMut};
+use crate::service::{ServiceApiContext, ServiceApiContextMut};
 
+pub mod access_log;
 pub mod actix_backend;
+pub mod aggregator;
+pub mod auth;
+pub mod batch;
+pub mod client;
+pub mod config;
 pub mod error;
+pub mod explorer;
+pub mod handler;
+pub mod pagination;
+pub mod proof;
 pub mod service;
+pub mod testing;
+pub mod warp_backend;
 
 pub type Result<I> = ::std::result::Result<I, error::Error>;
-pub type FutureResult<I> = Box<Future<Item = I, Error = error::Error>>;
+
+/// Result type of the asynchronous handlers, which return the future `Fut`
+/// resolving to `Result<I>`.
+pub struct Async<Fut>(PhantomData<Fut>);
 
 pub struct TypedFn<S, Q, I, R, F> {
     pub(crate) f: F,
-    _context_type: ::std::marker::PhantomData<S>,
-    _query_type: ::std::marker::PhantomData<Q>,
-    _item_type: ::std::marker::PhantomData<I>,
-    _result_type: ::std::marker::PhantomData<R>,
+    _context_type: PhantomData<S>,
+    _query_type: PhantomData<Q>,
+    _item_type: PhantomData<I>,
+    _result_type: PhantomData<R>,
 }
 
 pub struct NamedFn<S, Q, I, R, F> {
     pub(crate) name: &'static str,
+    /// HTTP method of the endpoint, the default one depends on the context type.
+    pub(crate) method: Option<http::Method>,
     pub(crate) inner: TypedFn<S, Q, I, R, F>,
 }
 
+impl<S, Q, I, R, F> TypedFn<S, Q, I, R, F> {
+    fn new(f: F) -> Self {
+        TypedFn {
+            f,
+            _context_type: PhantomData,
+            _query_type: PhantomData,
+            _item_type: PhantomData,
+            _result_type: PhantomData,
+        }
+    }
+}
+
+impl<S, Q, I, R, F: Clone> Clone for TypedFn<S, Q, I, R, F> {
+    fn clone(&self) -> Self {
+        TypedFn::new(self.f.clone())
+    }
+}
+
+impl<S, Q, I, R, F: Clone> Clone for NamedFn<S, Q, I, R, F> {
+    fn clone(&self) -> Self {
+        NamedFn {
+            name: self.name,
+            method: self.method.clone(),
+            inner: self.inner.clone(),
+        }
+    }
+}
+
 impl<Q, I, F> From<F> for TypedFn<ServiceApiContext, Q, I, Result<I>, F>
 where
     F: for<'r> Fn(&'r ServiceApiContext, Q) -> Result<I>,
 {
     fn from(f: F) -> Self {
-        TypedFn {
-            f,
-            _context_type: ::std::marker::PhantomData,
-            _query_type: ::std::marker::PhantomData,
-            _item_type: ::std::marker::PhantomData,
-            _result_type: ::std::marker::PhantomData,
-        }
+        TypedFn::new(f)
     }
 }
 
@@ -49,42 +82,28 @@ where
     F: for<'r> Fn(&'r ServiceApiContextMut, Q) -> Result<I>,
 {
     fn from(f: F) -> Self {
-        TypedFn {
-            f,
-            _context_type: ::std::marker::PhantomData,
-            _query_type: ::std::marker::PhantomData,
-            _item_type: ::std::marker::PhantomData,
-            _result_type: ::std::marker::PhantomData,
-        }
+        TypedFn::new(f)
     }
 }
 
-impl<Q, I, F> From<F> for TypedFn<ServiceApiContext, Q, I, FutureResult<I>, F>
+/// Asynchronous handlers take the context by value, so the returned future may outlive
+/// the request handler, as the `async fn` futures do.
+impl<Q, I, F, Fut> From<F> for TypedFn<ServiceApiContext, Q, I, Async<Fut>, F>
 where
-    F: for<'r> Fn(&'r ServiceApiContext, Q) -> FutureResult<I>,
+    F: Fn(ServiceApiContext, Q) -> Fut,
+    Fut: Future<Output = Result<I>>,
 {
     fn from(f: F) -> Self {
-        TypedFn {
-            f,
-            _context_type: ::std::marker::PhantomData,
-            _query_type: ::std::marker::PhantomData,
-            _item_type: ::std::marker::PhantomData,
-            _result_type: ::std::marker::PhantomData,
-        }
+        TypedFn::new(f)
     }
 }
 
-impl<Q, I, F> From<F> for TypedFn<ServiceApiContextMut, Q, I, FutureResult<I>, F>
+impl<Q, I, F, Fut> From<F> for TypedFn<ServiceApiContextMut, Q, I, Async<Fut>, F>
 where
-    F: for<'r> Fn(&'r ServiceApiContextMut, Q) -> FutureResult<I>,
+    F: Fn(ServiceApiContextMut, Q) -> Fut,
+    Fut: Future<Output = Result<I>>,
 {
     fn from(f: F) -> Self {
-        TypedFn {
-            f,
-            _context_type: ::std::marker::PhantomData,
-            _query_type: ::std::marker::PhantomData,
-            _item_type: ::std::marker::PhantomData,
-            _result_type: ::std::marker::PhantomData,
-        }
+        TypedFn::new(f)
     }
 }
@@ -1,90 +1,87 @@
-extern crate actix_web;
-extern crate serde;
-#[macro_use]
-extern crate serde_derive;
-extern crate api_builder;
-extern crate exonum;
-extern crate failure;
-extern crate futures;
-extern crate serde_urlencoded;
-
-use actix_web::*;
-use futures::Future;
-
+use serde_derive::{Deserialize, Serialize};
+
+use api_builder::actix_backend;
+use api_builder::aggregator::ApiAggregator;
+use api_builder::config::NodeConfig;
+use api_builder::error::Error;
+use api_builder::explorer::ExplorerService;
+use api_builder::pagination::{self, Page, PageQuery};
 use api_builder::service::{Service, ServiceApiContext, ServiceApiContextMut, ServiceApiInitializer};
+use api_builder::service_api;
 
 use exonum::blockchain::Blockchain;
+use exonum::crypto::Hash;
+use exonum::node::ApiSender;
+use exonum::storage::{Database, DbOptions, ListIndex, MemoryDB, RocksDB};
 
+use std::env;
 use std::sync::{Arc, Mutex};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct MyRequest {
     pub name: String,
     pub count: u64,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Seed {
     pub seed: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct MyResponse {
     name: String,
     value: u64,
 }
 
-pub trait MyServiceApi {
-    type Error;
+service_api! {
+    pub trait MyServiceApi for ServiceApiContext {
+        client MyServiceClient;
 
-    fn foo(&self, request: MyRequest) -> Result<MyResponse, Self::Error>;
+        fn foo(&self, request: MyRequest) -> MyResponse;
 
-    fn baz(&self, request: (String, String)) -> Result<String, Self::Error>;
+        fn baz(&self, request: (String, String)) -> String;
 
-    fn hello(&self, request: ()) -> Result<String, Self::Error>;
+        fn hello(&self, request: ()) -> String;
 
-    fn hello_async(&self, request: ()) -> Box<Future<Item = String, Error = Self::Error>>;
+        /// Returns the page of the hashes pushed into the `foo` list by the `bar` endpoint.
+        fn hashes(&self, request: PageQuery) -> Page<Hash>;
+    }
 }
 
-pub trait MyServiceApiMut {
-    type Error;
-
-    fn bar(&self, Seed) -> Result<(u64, exonum::crypto::Hash), Self::Error>;
+service_api! {
+    pub trait MyServiceApiMut for ServiceApiContextMut {
+        client MyServiceMutClient;
 
-    fn bar_async(
-        &self,
-        Seed,
-    ) -> Box<Future<Item = (u64, exonum::crypto::Hash), Error = Self::Error>>;
+        fn bar(&self, request: Seed) -> (u64, Hash);
+    }
 }
 
 impl MyServiceApi for ServiceApiContext {
-    type Error = failure::Error;
-
-    fn foo(&self, request: MyRequest) -> Result<MyResponse, Self::Error> {
+    fn foo(&self, request: MyRequest) -> Result<MyResponse, Error> {
         Ok(MyResponse {
             name: request.name,
             value: request.count * 2,
         })
     }
 
-    fn baz(&self, request: (String, String)) -> Result<String, Self::Error> {
+    fn baz(&self, request: (String, String)) -> Result<String, Error> {
         Ok(format!("first is {}, second id {}", request.0, request.1))
     }
 
-    fn hello(&self, _: ()) -> Result<String, failure::Error> {
+    fn hello(&self, _: ()) -> Result<String, Error> {
         Ok("Hello Actix".to_owned())
     }
 
-    fn hello_async(&self, _: ()) -> Box<Future<Item = String, Error = Self::Error>> {
-        let future = futures::lazy(|| Ok("Hello async response".to_owned()));
-        Box::new(future)
+    fn hashes(&self, request: PageQuery) -> Result<Page<Hash>, Error> {
+        let snapshot = self.blockchain.snapshot();
+        let index = ListIndex::new("foo", &snapshot);
+        pagination::list_page(&index, &request)
     }
 }
 
 impl MyServiceApiMut for ServiceApiContextMut {
-    type Error = failure::Error;
-
-    fn bar(&self, request: Seed) -> Result<(u64, exonum::crypto::Hash), Self::Error> {
+    fn bar(&self, request: Seed) -> Result<(u64, Hash), Error> {
         let hash = exonum::crypto::hash(request.seed.as_bytes());
         let mut fork = self.blockchain.fork();
         let len = {
@@ -92,30 +89,35 @@ impl MyServiceApiMut for ServiceApiContextMut {
             index.push(hash);
             index.len()
         };
-        self.blockchain.clone().merge(fork.into_patch())?;
+        self.blockchain
+            .clone()
+            .merge(fork.into_patch())
+            .map_err(Error::internal)?;
         Ok((len, hash))
     }
+}
 
-    fn bar_async(
-        &self,
-        request: Seed,
-    ) -> Box<Future<Item = (u64, exonum::crypto::Hash), Error = Self::Error>> {
-        let self_ = self.clone();
-        let future = futures::lazy(move || self_.bar(request));
-        Box::new(future)
-    }
+async fn hello_async(_: ServiceApiContext, _: ()) -> Result<String, Error> {
+    Ok("Hello async response".to_owned())
+}
+
+async fn bar_async(
+    context: ServiceApiContextMut,
+    request: Seed,
+) -> Result<(u64, Hash), Error> {
+    context.bar(request)
 }
 
 pub struct MyService;
 
-#[derive(Default, Clone)]
+#[derive(Default)]
 pub struct SharedState {
-    count: Arc<Mutex<u64>>,
+    count: Mutex<u64>,
 }
 
 impl SharedState {
     fn new() -> SharedState {
-        println!("Created shared state");
+        log::debug!("Created shared state");
         SharedState::default()
     }
 
@@ -126,72 +128,110 @@ impl SharedState {
     fn count(&self) -> u64 {
         *self.count.lock().unwrap()
     }
+
+    /// Resets the counter and returns its previous value.
+    fn reset(&self) -> u64 {
+        ::std::mem::take(&mut *self.count.lock().unwrap())
+    }
+}
+
+fn counter(context: &ServiceApiContextMut, _: String) -> Result<u64, Error> {
+    let shared_state = context.state::<SharedState>()?;
+    let count = shared_state.count();
+    shared_state.increment();
+    log::info!(
+        "request_id={} shared state is incremented to {}",
+        context.request_id().unwrap_or("-"),
+        shared_state.count()
+    );
+    Ok(count)
+}
+
+/// Administrative endpoint, which is served by the private API only.
+fn reset_counter(context: &ServiceApiContextMut, _: ()) -> Result<u64, Error> {
+    let count = context.state::<SharedState>()?.reset();
+    log::info!(
+        "request_id={} shared state is reset from {}",
+        context.request_id().unwrap_or("-"),
+        count
+    );
+    Ok(count)
 }
 
 impl Service for MyService {
+    fn service_name(&self) -> &str {
+        "rustfest"
+    }
+
     fn initialize_api(&self, initializer: &mut ServiceApiInitializer) {
-        println!("Initialize api");
-        let public_api = initializer.public_api();
-
-        let shared_state = SharedState::new();
-        let stateful_endpoint =
-            move |_: &ServiceApiContextMut, _: String| -> Result<(u64), failure::Error> {
-                let count = shared_state.count();
-                shared_state.increment();
-                println!("Increment shared state: {}", shared_state.count());
-                Ok(count)
-            };
-
-        public_api
-            .endpoint("foo", <ServiceApiContext as MyServiceApi>::foo)
-            .endpoint("hello", <ServiceApiContext as MyServiceApi>::hello)
-            .endpoint(
-                "hello_async",
-                <ServiceApiContext as MyServiceApi>::hello_async,
-            )
-            .endpoint("baz", <ServiceApiContext as MyServiceApi>::baz)
-            .endpoint("bar", <ServiceApiContextMut as MyServiceApiMut>::bar)
-            .endpoint(
-                "bar_async",
-                <ServiceApiContextMut as MyServiceApiMut>::bar_async,
-            )
-            .endpoint("counter", stateful_endpoint);
+        log::debug!("Initialize api of the {} service", self.service_name());
+        initializer.state(SharedState::new());
+
+        <ServiceApiContext as MyServiceApi>::wire(initializer.public_api());
+        <ServiceApiContextMut as MyServiceApiMut>::wire(initializer.public_api());
+        initializer
+            .public_api()
+            .endpoint_async("hello_async", hello_async)
+            .endpoint("counter", counter)
+            .endpoint_async("bar_async", bar_async);
+        initializer
+            .private_api()
+            .endpoint("reset_counter", reset_counter);
     }
 }
 
-fn main() {
-    exonum::helpers::init_logger().unwrap();
-
-    let keypair = exonum::crypto::gen_keypair();
-    let api_sender = exonum::node::ApiSender::new(futures::sync::mpsc::channel(1).0);
-    let db = exonum::storage::RocksDB::open("/tmp/actix", &exonum::storage::DbOptions::default())
-        .unwrap();
-    let blockchain = Blockchain::new(db, vec![], keypair.0, keypair.1, api_sender);
-
-    let mut initalizer = ServiceApiInitializer::default();
-
-    let service = MyService;
-    service.initialize_api(&mut initalizer);
-
-    let endpoints = Arc::from(Mutex::from(
-        initalizer.public_api_builder.web_backend.finish(),
-    ));
-    let context = ServiceApiContextMut::new(blockchain.clone());
-    server::new(move || {
-        let context = context.clone();
-        let endpoints = endpoints.clone();
-        App::with_state(context).scope("api", move |scope| {
-            scope.nested("rustfest", move |mut scope| {
-                let endpoints = endpoints.lock().unwrap().clone();
-                for endpoint in endpoints {
-                    scope = scope.route(endpoint.name, endpoint.method.clone(), move |request| {
-                        (endpoint.inner)(request)
-                    });
-                }
-                scope
-            })
-        })
-    }).bind("localhost:8080")
-        .unwrap()
-        .run()
+#[actix_web::main]
+async fn main() -> Result<(), failure::Error> {
+    let config = match NodeConfig::from_args(env::args_os()) {
+        Ok(config) => config,
+        // Help and version are reported as the clap errors as well.
+        Err(e) => match e.downcast::<clap::Error>() {
+            Ok(e) => e.exit(),
+            Err(e) => return Err(e),
+        },
+    };
+    if let Some(ref level) = config.log_level {
+        env::set_var("RUST_LOG", level);
+    }
+    exonum::helpers::init_logger()?;
+
+    // The data is kept in memory unless the database path is given.
+    let database: Arc<dyn Database> = match config.database_path {
+        Some(ref path) => Arc::new(RocksDB::open(path, &DbOptions::default())?),
+        None => Arc::new(MemoryDB::new()),
+    };
+    let keys = config.service_keys()?;
+    let api_sender = ApiSender::new(futures01::sync::mpsc::channel(1).0);
+    let blockchain = Blockchain::new(
+        database,
+        vec![],
+        keys.public_key,
+        keys.secret_key,
+        api_sender.clone(),
+    );
+
+    let services: Vec<Box<dyn Service>> = vec![Box::new(MyService), Box::new(ExplorerService)];
+    let aggregator = ApiAggregator::new(&services)?;
+
+    let blocking_threads = config.max_blocking_threads()?;
+    let context = ServiceApiContextMut::new(blockchain, api_sender);
+    let public_server = actix_backend::start_server(
+        &config.public_api_address,
+        Some(blocking_threads),
+        context.clone(),
+        aggregator.public_api(),
+    )?;
+    let private_server = actix_backend::start_server(
+        &config.private_api_address,
+        Some(blocking_threads),
+        context,
+        aggregator.private_api(),
+    )?;
+    log::info!(
+        "Serving the public API on {} and the private one on {}",
+        config.public_api_address,
+        config.private_api_address
+    );
+    futures::future::try_join(public_server, private_server).await?;
+    Ok(())
 }
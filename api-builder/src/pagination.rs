@@ -0,0 +1,82 @@
+//! Pagination of the endpoints, which read the contents of the storage indexes.
+
+use exonum::storage::{ListIndex, MapIndex, Snapshot, StorageKey, StorageValue};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::Result;
+
+/// Number of the items in the page if the query has no limit.
+pub const DEFAULT_PAGE_LIMIT: usize = 100;
+/// Maximum number of the items in the single page.
+pub const MAX_PAGE_LIMIT: usize = 1000;
+
+/// Query of the page, which starts from the `cursor` item or from the first one.
+/// The cursor is the offset of the item for the `ListIndex` and the key for the `MapIndex`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PageQuery<C = u64> {
+    pub cursor: Option<C>,
+    pub limit: Option<usize>,
+}
+
+impl<C> PageQuery<C> {
+    /// Returns the number of the items in the page, if the limit is correct.
+    pub fn limit(&self) -> Result<usize> {
+        match self.limit.unwrap_or(DEFAULT_PAGE_LIMIT) {
+            0 => Err(Error::bad_request("Page limit should be positive")),
+            limit if limit > MAX_PAGE_LIMIT => Err(Error::bad_request(format!(
+                "Max page limit exceeded ({})",
+                MAX_PAGE_LIMIT
+            ))),
+            limit => Ok(limit),
+        }
+    }
+}
+
+/// Page of the items along with the cursor of the next page, which is absent
+/// for the last page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Page<T, C = u64> {
+    pub items: Vec<T>,
+    pub next: Option<C>,
+}
+
+/// Reads the page of the list elements.
+pub fn list_page<T, V>(index: &ListIndex<T, V>, query: &PageQuery) -> Result<Page<V>>
+where
+    T: AsRef<dyn Snapshot>,
+    V: StorageValue,
+{
+    let limit = query.limit()?;
+    let from = query.cursor.unwrap_or(0);
+    let items = index.iter_from(from).take(limit).collect::<Vec<_>>();
+    let next = from + items.len() as u64;
+    Ok(Page {
+        next: if next < index.len() { Some(next) } else { None },
+        items,
+    })
+}
+
+/// Reads the page of the map entries in the order of the keys.
+pub fn map_page<T, K, V>(
+    index: &MapIndex<T, K, V>,
+    query: &PageQuery<K>,
+) -> Result<Page<(K, V), K>>
+where
+    T: AsRef<dyn Snapshot>,
+    K: StorageKey + ToOwned<Owned = K>,
+    V: StorageValue,
+{
+    let limit = query.limit()?;
+    let mut items = match query.cursor {
+        Some(ref from) => index.iter_from(from).take(limit + 1).collect::<Vec<_>>(),
+        None => index.iter().take(limit + 1).collect::<Vec<_>>(),
+    };
+    // The extra entry is the first one of the next page.
+    let next = if items.len() > limit {
+        items.pop().map(|(key, _)| key)
+    } else {
+        None
+    };
+    Ok(Page { items, next })
+}
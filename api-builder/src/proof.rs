@@ -0,0 +1,458 @@
+use exonum::blockchain::{BlockProof, Blockchain, Schema};
+use exonum::crypto::{self, CryptoHash, Hash, HashStream, PublicKey, HASH_SIZE};
+use exonum::messages::Message;
+use exonum::storage::proof_map_index::{ProofMapKey, ProofPath, PROOF_MAP_KEY_SIZE};
+use exonum::storage::{ListProof, ProofListIndex, ProofMapIndex, Snapshot, StorageValue};
+use serde::de::{self, DeserializeOwned, Deserializer, Unexpected, Visitor};
+use serde::ser::Serializer;
+use serde_derive::{Deserialize, Serialize};
+
+use std::cmp::{self, Ordering};
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::error::Error;
+use crate::service::ServiceApiContext;
+use crate::Result;
+
+/// Length in bits of the paths to the leaves of the map.
+const LEAF_PATH_LEN: u16 = PROOF_MAP_KEY_SIZE as u16 * 8;
+
+/// Proof of the service table contents, anchored to the latest committed block.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StateProof<P> {
+    /// Block along with the precommits of the validators that accepted it.
+    pub block_proof: BlockProof,
+    /// Proof of the table root hash in the blockchain state.
+    pub to_table: MapEntryProof<Hash, Hash>,
+    /// Proof of the requested values in the table.
+    pub to_value: P,
+}
+
+/// Proof of the `ProofMapIndex` entry, which shows either the value of the key
+/// or its absence in the map.
+///
+/// Unlike `MapProof` of exonum 0.7.1, which fails to deserialize the paths to the leaves,
+/// it survives the JSON round trip.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MapEntryProof<K, V> {
+    pub key: K,
+    /// Value of the key, `None` if the key is missing in the map.
+    pub value: Option<V>,
+    /// Subtrees of the map, which are needed to restore its root hash, sorted by the paths.
+    pub proof: Vec<MapProofNode>,
+}
+
+/// Subtree of the map along with its root hash.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MapProofNode {
+    pub path: MapProofPath,
+    pub hash: Hash,
+}
+
+/// Path from the root of the map to the subtree, which is serialized as the string of bits
+/// like the exonum `ProofPath`. The paths of the key length lead to the leaves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MapProofPath {
+    key: [u8; PROOF_MAP_KEY_SIZE],
+    len: u16,
+}
+
+/// Proof of the `ProofListIndex` element, the list length is needed to check it.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound(deserialize = "V: DeserializeOwned"))]
+pub struct ListElementProof<V> {
+    pub proof: ListProof<V>,
+    pub len: u64,
+}
+
+pub type MapStateProof<K, V> = StateProof<MapEntryProof<K, V>>;
+pub type ListStateProof<V> = StateProof<ListElementProof<V>>;
+
+impl MapProofPath {
+    /// Path to the leaf of the key.
+    fn leaf<K: ProofMapKey>(key: &K) -> MapProofPath {
+        let mut bytes = [0; PROOF_MAP_KEY_SIZE];
+        key.write_key(&mut bytes);
+        MapProofPath {
+            key: bytes,
+            len: LEAF_PATH_LEN,
+        }
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.len == LEAF_PATH_LEN
+    }
+
+    fn bit(&self, index: u16) -> bool {
+        let index = usize::from(index);
+        (self.key[index / 8] >> (index % 8)) & 1 == 1
+    }
+
+    /// Returns the path cut to the given length, the bits after the end are zeroed.
+    fn prefix(&self, len: u16) -> MapProofPath {
+        let mut path = MapProofPath {
+            key: [0; PROOF_MAP_KEY_SIZE],
+            len,
+        };
+        for index in (0..len).filter(|&index| self.bit(index)) {
+            let index = usize::from(index);
+            path.key[index / 8] |= 1 << (index % 8);
+        }
+        path
+    }
+
+    fn common_prefix_len(&self, other: &MapProofPath) -> u16 {
+        let len = cmp::min(self.len, other.len);
+        (0..len)
+            .find(|&index| self.bit(index) != other.bit(index))
+            .unwrap_or(len)
+    }
+
+    fn starts_with(&self, other: &MapProofPath) -> bool {
+        self.common_prefix_len(other) == other.len
+    }
+
+    /// Binary layout of the exonum `ProofPath`, which the node hashes are calculated from.
+    fn to_bytes(&self) -> [u8; PROOF_MAP_KEY_SIZE + 2] {
+        let mut bytes = [0; PROOF_MAP_KEY_SIZE + 2];
+        bytes[1..=PROOF_MAP_KEY_SIZE].copy_from_slice(&self.key);
+        if self.is_leaf() {
+            bytes[0] = 1;
+        } else {
+            bytes[PROOF_MAP_KEY_SIZE + 1] = self.len as u8;
+        }
+        bytes
+    }
+}
+
+impl<'a> From<&'a ProofPath> for MapProofPath {
+    fn from(path: &'a ProofPath) -> MapProofPath {
+        let bytes = path.as_bytes();
+        let mut key = [0; PROOF_MAP_KEY_SIZE];
+        key.copy_from_slice(&bytes[1..=PROOF_MAP_KEY_SIZE]);
+        let len = if path.is_leaf() {
+            LEAF_PATH_LEN
+        } else {
+            u16::from(bytes[PROOF_MAP_KEY_SIZE + 1])
+        };
+        MapProofPath { key, len }.prefix(len)
+    }
+}
+
+/// Orders the paths as the exonum `ProofPath` does: by the bits, then the prefixes first.
+impl Ord for MapProofPath {
+    fn cmp(&self, other: &MapProofPath) -> Ordering {
+        let common_len = self.common_prefix_len(other);
+        if common_len < cmp::min(self.len, other.len) {
+            self.bit(common_len).cmp(&other.bit(common_len))
+        } else {
+            self.len.cmp(&other.len)
+        }
+    }
+}
+
+impl PartialOrd for MapProofPath {
+    fn partial_cmp(&self, other: &MapProofPath) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl serde::Serialize for MapProofPath {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let bits = (0..self.len)
+            .map(|index| if self.bit(index) { '1' } else { '0' })
+            .collect::<String>();
+        serializer.serialize_str(&bits)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for MapProofPath {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct PathVisitor;
+
+        impl<'de> Visitor<'de> for PathVisitor {
+            type Value = MapProofPath;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter, "string of 1 to {} binary digits", LEAF_PATH_LEN)
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> std::result::Result<MapProofPath, E> {
+                if value.is_empty() || value.len() > usize::from(LEAF_PATH_LEN) {
+                    return Err(E::invalid_value(Unexpected::Str(value), &self));
+                }
+                let mut key = [0; PROOF_MAP_KEY_SIZE];
+                for (index, bit) in value.bytes().enumerate() {
+                    match bit {
+                        b'0' => {}
+                        b'1' => key[index / 8] |= 1 << (index % 8),
+                        _ => return Err(E::invalid_value(Unexpected::Str(value), &self)),
+                    }
+                }
+                Ok(MapProofPath {
+                    key,
+                    len: value.len() as u16,
+                })
+            }
+        }
+
+        deserializer.deserialize_str(PathVisitor)
+    }
+}
+
+impl<K, V> MapEntryProof<K, V>
+where
+    K: ProofMapKey + Clone,
+    V: StorageValue,
+{
+    /// Creates the proof of the key in the map.
+    pub fn new<T: AsRef<dyn Snapshot>>(index: &ProofMapIndex<T, K, V>, key: K) -> Self {
+        let value = index.get(&key);
+        let proof = index
+            .get_proof(key.clone())
+            .proof_unchecked()
+            .iter()
+            .map(|(path, hash)| MapProofNode {
+                path: MapProofPath::from(path),
+                hash: *hash,
+            })
+            .collect();
+        MapEntryProof { key, value, proof }
+    }
+}
+
+impl<K: ProofMapKey, V: StorageValue> MapEntryProof<K, V> {
+    /// Checks the structure of the proof and restores the root hash of the map.
+    pub fn merkle_root(&self) -> Result<Hash> {
+        let invalid =
+            |reason: &str| Err(Error::bad_request(format!("Invalid map proof: {}", reason)));
+
+        let nodes = &self.proof;
+        for pair in nodes.windows(2) {
+            if pair[0].path >= pair[1].path {
+                return invalid("subtrees are not sorted by the paths");
+            }
+            if pair[1].path.starts_with(&pair[0].path) {
+                return invalid("subtree is embedded into the other one");
+            }
+        }
+        let path = MapProofPath::leaf(&self.key);
+        if nodes.iter().any(|node| path.starts_with(&node.path)) {
+            return invalid("key is embedded into the subtree");
+        }
+
+        let mut nodes = nodes.clone();
+        if let Some(ref value) = self.value {
+            let position = nodes.binary_search_by(|node| node.path.cmp(&path));
+            let position = position.unwrap_or_else(|position| position);
+            nodes.insert(
+                position,
+                MapProofNode {
+                    path,
+                    hash: value.hash(),
+                },
+            );
+        }
+
+        match nodes.as_slice() {
+            [] => Ok(Hash::default()),
+            [node] if node.path.is_leaf() => Ok(HashStream::new()
+                .update(&node.path.to_bytes())
+                .update(node.hash.as_ref())
+                .hash()),
+            [_] => invalid("single subtree of the map is not a leaf"),
+            _ => Ok(fold_nodes(&nodes)),
+        }
+    }
+}
+
+/// Restores the root hash of the map from its sorted subtrees as `MapProof::check` of exonum
+/// does: the last two subtrees of the contour are folded into their branch while they have
+/// the longer common prefix than the last one and the next subtree.
+fn fold_nodes(nodes: &[MapProofNode]) -> Hash {
+    fn fold(contour: &mut Vec<MapProofNode>) {
+        let right = contour.pop().unwrap();
+        let left = contour.pop().unwrap();
+        let mut branch = Vec::with_capacity(2 * (HASH_SIZE + PROOF_MAP_KEY_SIZE + 2));
+        branch.extend_from_slice(left.hash.as_ref());
+        branch.extend_from_slice(right.hash.as_ref());
+        branch.extend_from_slice(&left.path.to_bytes());
+        branch.extend_from_slice(&right.path.to_bytes());
+        contour.push(MapProofNode {
+            path: left.path.prefix(left.path.common_prefix_len(&right.path)),
+            hash: crypto::hash(&branch),
+        });
+    }
+
+    let mut contour: Vec<MapProofNode> = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        while let [.., left, right] = contour.as_slice() {
+            if left.path.common_prefix_len(&right.path) <= right.path.common_prefix_len(&node.path)
+            {
+                break;
+            }
+            fold(&mut contour);
+        }
+        contour.push(*node);
+    }
+    while contour.len() > 1 {
+        fold(&mut contour);
+    }
+    contour[0].hash
+}
+
+impl<P> StateProof<P> {
+    /// Anchors the proof of the `table_idx` table of the service to the latest block.
+    ///
+    /// Fails if the genesis block is not created yet.
+    pub fn new(
+        snapshot: &dyn Snapshot,
+        service_id: u16,
+        table_idx: usize,
+        to_value: P,
+    ) -> Result<Self> {
+        let schema = Schema::new(snapshot);
+        if schema.block_hashes_by_height().is_empty() {
+            return Err(Error::not_found("Genesis block is not created yet"));
+        }
+        let block_proof = schema
+            .block_and_precommits(schema.height())
+            .ok_or_else(|| Error::internal("Latest block is missing"))?;
+        let table_key = Blockchain::service_table_unique_key(service_id, table_idx);
+        Ok(StateProof {
+            block_proof,
+            to_table: MapEntryProof::new(&schema.state_hash_aggregator(), table_key),
+            to_value,
+        })
+    }
+
+    /// Checks the block precommits and returns the proven root hash of the service table.
+    fn verify_table(
+        &self,
+        validators: &[PublicKey],
+        service_id: u16,
+        table_idx: usize,
+    ) -> Result<Hash> {
+        let block = &self.block_proof.block;
+        let block_hash = block.hash();
+
+        let mut voted = HashSet::new();
+        for precommit in &self.block_proof.precommits {
+            let validator = precommit.validator().0 as usize;
+            let public_key = validators
+                .get(validator)
+                .ok_or_else(|| Error::bad_request("Precommit from the unknown validator"))?;
+            if precommit.height() != block.height()
+                || *precommit.block_hash() != block_hash
+                || !precommit.verify_signature(public_key)
+            {
+                return Err(Error::bad_request("Precommit does not match the block"));
+            }
+            voted.insert(validator);
+        }
+        if voted.len() < validators.len() * 2 / 3 + 1 {
+            return Err(Error::bad_request(
+                "Block is not accepted by the validators majority",
+            ));
+        }
+
+        if self.to_table.key != Blockchain::service_table_unique_key(service_id, table_idx) {
+            return Err(Error::bad_request(
+                "Table proof is given for the other table",
+            ));
+        }
+        if self.to_table.merkle_root()? != *block.state_hash() {
+            return Err(Error::bad_request(
+                "Table proof does not match the block state hash",
+            ));
+        }
+        self.to_table
+            .value
+            .ok_or_else(|| Error::bad_request("Table is missing in the blockchain state"))
+    }
+}
+
+impl<K: ProofMapKey, V: StorageValue> StateProof<MapEntryProof<K, V>> {
+    /// Verifies the proof using the consensus keys of the validators and returns the proven value,
+    /// which is `None` if the key is missing in the table.
+    pub fn verify(
+        &self,
+        validators: &[PublicKey],
+        service_id: u16,
+        table_idx: usize,
+    ) -> Result<Option<&V>> {
+        let table_hash = self.verify_table(validators, service_id, table_idx)?;
+        if self.to_value.merkle_root()? != table_hash {
+            return Err(Error::bad_request(
+                "Value proof does not match the table hash",
+            ));
+        }
+        Ok(self.to_value.value.as_ref())
+    }
+}
+
+impl<V: StorageValue> StateProof<ListElementProof<V>> {
+    /// Verifies the proof using the consensus keys of the validators and returns the proven elements.
+    pub fn verify(
+        &self,
+        validators: &[PublicKey],
+        service_id: u16,
+        table_idx: usize,
+    ) -> Result<Vec<(u64, &V)>> {
+        let table_hash = self.verify_table(validators, service_id, table_idx)?;
+        self.to_value
+            .proof
+            .validate(table_hash, self.to_value.len)
+            .map_err(|e| Error::bad_request(format!("Invalid list proof: {:?}", e)))
+    }
+}
+
+impl ServiceApiContext {
+    /// Returns the proof of the value in the `ProofMapIndex`, which is the `table_idx` table
+    /// of the service.
+    pub fn map_proof<K, V>(
+        &self,
+        service_id: u16,
+        table_idx: usize,
+        index_name: &str,
+        key: K,
+    ) -> Result<MapStateProof<K, V>>
+    where
+        K: ProofMapKey + Clone,
+        V: StorageValue,
+    {
+        let snapshot = self.blockchain.snapshot();
+        let to_value = MapEntryProof::new(&ProofMapIndex::new(index_name, &snapshot), key);
+        StateProof::new(snapshot.as_ref(), service_id, table_idx, to_value)
+    }
+
+    /// Returns the proof of the element in the `ProofListIndex`, which is the `table_idx` table
+    /// of the service.
+    ///
+    /// Fails with the `NotFound` error if the index is out of the list bounds.
+    pub fn list_proof<V: StorageValue>(
+        &self,
+        service_id: u16,
+        table_idx: usize,
+        index_name: &str,
+        index: u64,
+    ) -> Result<ListStateProof<V>> {
+        let snapshot = self.blockchain.snapshot();
+        let to_value = {
+            let list = ProofListIndex::new(index_name, &snapshot);
+            let len = list.len();
+            if index >= len {
+                return Err(Error::not_found(format!(
+                    "Element {} is out of the list bounds, the list length is {}",
+                    index, len
+                )));
+            }
+            ListElementProof {
+                proof: list.get_proof(index),
+                len,
+            }
+        };
+        StateProof::new(snapshot.as_ref(), service_id, table_idx, to_value)
+    }
+}
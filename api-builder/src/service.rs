@@ -1,16 +1,159 @@
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use serde_derive::{Deserialize, Serialize};
 
+use std::any::{type_name, Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
 use std::ops::Deref;
+use std::sync::Arc;
 
-use exonum::blockchain::Blockchain;
+use exonum::blockchain::{Blockchain, Transaction};
+use exonum::crypto::{self, Hash, PublicKey};
+use exonum::node::{ApiSender, ExternalMessage};
+use exonum::storage::Database;
+use http::Method;
 
-use actix_backend;
-use {NamedFn, TypedFn};
+use crate::actix_backend;
+use crate::error::Error;
+use crate::{Async, NamedFn, Result, TypedFn};
+
+type StateMap = HashMap<TypeId, Box<dyn Any + Send + Sync>>;
+
+/// Typed state of the service, which is shared by all its endpoints.
+#[derive(Clone, Default)]
+pub struct ServiceState {
+    values: Arc<StateMap>,
+}
+
+impl ServiceState {
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.values
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref())
+    }
+}
+
+impl fmt::Debug for ServiceState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ServiceState")
+            .field("len", &self.values.len())
+            .finish()
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct ServiceApiContext {
     pub blockchain: Blockchain,
+    pub api_sender: ApiSender,
+    /// State of the service, which handles the current request.
+    pub(crate) state: ServiceState,
+    /// ID of the current request, see `access_log::REQUEST_ID_HEADER`.
+    pub(crate) request_id: Option<String>,
+    /// Public key of the client, which has signed the current request, see `auth`.
+    pub(crate) caller: Option<PublicKey>,
+    /// Whether the synchronous handlers of the actix backend are run on the blocking threads,
+    /// see `ServiceApiContextMut::with_blocking_handlers`.
+    pub(crate) blocking: bool,
+}
+
+impl ServiceApiContext {
+    /// Returns the state registered by the service with `ServiceApiInitializer::state`.
+    pub fn state<T: Any>(&self) -> Result<&T> {
+        self.state.get().ok_or_else(|| {
+            Error::internal(format!("State `{}` is not registered", type_name::<T>()))
+        })
+    }
+
+    /// Returns the ID of the request, which is handled with this context.
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    /// Returns the public key of the client, which has signed the request,
+    /// or `None` for the anonymous request.
+    pub fn caller(&self) -> Option<&PublicKey> {
+        self.caller.as_ref()
+    }
+
+    /// Returns the public key of the client for the endpoints, which accept
+    /// the signed requests only.
+    pub fn signed_caller(&self) -> Result<&PublicKey> {
+        self.caller
+            .as_ref()
+            .ok_or_else(|| Error::unauthorized("Request should be signed"))
+    }
+}
+
+/// Response of the transaction submission endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TransactionResponse {
+    pub tx_hash: Hash,
+}
+
+/// Handler of the transaction submission endpoint.
+pub type TransactionFn<T> = fn(&ServiceApiContextMut, T) -> Result<TransactionResponse>;
+
+fn submit_transaction<T>(
+    context: &ServiceApiContextMut,
+    transaction: T,
+) -> Result<TransactionResponse>
+where
+    T: Into<Box<dyn Transaction>>,
+{
+    context
+        .send_transaction(transaction.into())
+        .map(|tx_hash| TransactionResponse { tx_hash })
+}
+
+/// Signature of the endpoint handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EndpointMeta {
+    /// Whether the handler takes the mutable context.
+    pub mutable: bool,
+    /// Whether the handler is asynchronous.
+    pub asynchronous: bool,
+    pub query_type: &'static str,
+    pub response_type: &'static str,
+}
+
+impl EndpointMeta {
+    pub fn new<Q, I>(mutable: bool, asynchronous: bool) -> EndpointMeta {
+        EndpointMeta {
+            mutable,
+            asynchronous,
+            query_type: type_name::<Q>(),
+            response_type: type_name::<I>(),
+        }
+    }
+}
+
+/// Description of the endpoint, which is served by the `/_endpoints` listing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EndpointInfo {
+    pub name: String,
+    pub path: String,
+    pub method: String,
+    pub mutable: bool,
+    pub asynchronous: bool,
+    pub query_type: String,
+    pub response_type: String,
+}
+
+impl EndpointInfo {
+    /// Describes the endpoint mounted under the given `api/<prefix>` scope.
+    pub fn new(prefix: &str, name: &str, method: &Method, meta: &EndpointMeta) -> EndpointInfo {
+        EndpointInfo {
+            name: name.to_owned(),
+            path: format!("/api/{}/{}", prefix, name),
+            method: method.to_string(),
+            mutable: meta.mutable,
+            asynchronous: meta.asynchronous,
+            query_type: meta.query_type.to_owned(),
+            response_type: meta.response_type.to_owned(),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -19,10 +162,69 @@ pub struct ServiceApiContextMut {
 }
 
 impl ServiceApiContextMut {
-    pub fn new(blockchain: Blockchain) -> ServiceApiContextMut {
+    pub fn new(blockchain: Blockchain, api_sender: ApiSender) -> ServiceApiContextMut {
         ServiceApiContextMut {
-            inner: ServiceApiContext { blockchain },
+            inner: ServiceApiContext {
+                blockchain,
+                api_sender,
+                state: ServiceState::default(),
+                request_id: None,
+                caller: None,
+                blocking: false,
+            },
+        }
+    }
+
+    /// Creates the context for the blockchain without services, which is stored in the given
+    /// database. The service keys of the blockchain are generated randomly.
+    pub fn with_database<D: Into<Arc<dyn Database>>>(
+        database: D,
+        api_sender: ApiSender,
+    ) -> ServiceApiContextMut {
+        let (public_key, secret_key) = crypto::gen_keypair();
+        let blockchain = Blockchain::new(
+            database,
+            vec![],
+            public_key,
+            secret_key,
+            api_sender.clone(),
+        );
+        ServiceApiContextMut::new(blockchain, api_sender)
+    }
+
+    /// Runs the synchronous handlers of the actix backend with `actix_web::web::block`,
+    /// so they do not block the event loop of the server. The number of the blocking threads
+    /// is the one of the server, see `actix_backend::start_server`. The warp backend runs
+    /// the synchronous handlers in place on the event loop regardless of this setting.
+    pub fn with_blocking_handlers(mut self) -> ServiceApiContextMut {
+        self.inner.blocking = true;
+        self
+    }
+
+    /// Verifies the transaction and hands it to the node, which broadcasts it to the network.
+    ///
+    /// Only the mutable endpoints may submit the transactions, the read-only ones cannot:
+    ///
+    /// ```compile_fail
+    /// use api_builder::service::ServiceApiContext;
+    /// use exonum::blockchain::Transaction;
+    ///
+    /// fn submit(context: &ServiceApiContext, transaction: Box<dyn Transaction>) {
+    ///     context.send_transaction(transaction).unwrap();
+    /// }
+    /// ```
+    pub fn send_transaction(&self, transaction: Box<dyn Transaction>) -> Result<Hash> {
+        let tx_hash = transaction.hash();
+        if !transaction.verify() {
+            return Err(Error::bad_request(format!(
+                "Unable to verify transaction {}",
+                tx_hash.to_hex()
+            )));
         }
+        self.api_sender
+            .send_external_message(ExternalMessage::Transaction(transaction))
+            .map_err(Error::internal)?;
+        Ok(tx_hash)
     }
 }
 
@@ -34,8 +236,20 @@ impl Deref for ServiceApiContextMut {
     }
 }
 
+/// Handler of the endpoint, which is collected by the `ApiAggregator`.
+pub trait EndpointHandler: Clone {
+    fn name(&self) -> &'static str;
+
+    fn method(&self) -> &Method;
+
+    fn meta(&self) -> &EndpointMeta;
+
+    /// Makes the state of the service available to the handler through the context.
+    fn with_state(self, state: ServiceState) -> Self;
+}
+
 pub trait ServiceApiBackend: Sized {
-    type Handler;
+    type Handler: EndpointHandler;
 
     fn endpoint<S, Q, I, R, F, E>(&mut self, name: &'static str, e: E) -> &mut Self
     where
@@ -47,44 +261,226 @@ pub trait ServiceApiBackend: Sized {
     {
         let named_fn = NamedFn {
             name,
+            method: None,
+            inner: e.into(),
+        };
+        self.raw_handler(Self::Handler::from(named_fn))
+    }
+
+    /// Adds the endpoint with the explicitly specified HTTP method.
+    ///
+    /// The query is taken from the query string for the `GET` and `DELETE` methods
+    /// and from the JSON body for the other ones. The handlers taking `ServiceApiContextMut`
+    /// cannot be bound to the safe methods like `GET`, such services are rejected by
+    /// the `ApiAggregator`.
+    fn endpoint_with<S, Q, I, R, F, E>(
+        &mut self,
+        name: &'static str,
+        method: Method,
+        e: E,
+    ) -> &mut Self
+    where
+        Q: DeserializeOwned + 'static,
+        I: Serialize + 'static,
+        F: for<'r> Fn(&'r S, Q) -> R + 'static + Clone,
+        E: Into<TypedFn<S, Q, I, R, F>>,
+        Self::Handler: From<NamedFn<S, Q, I, R, F>>,
+    {
+        let named_fn = NamedFn {
+            name,
+            method: Some(method),
+            inner: e.into(),
+        };
+        self.raw_handler(Self::Handler::from(named_fn))
+    }
+
+    /// Adds the endpoint, which takes the context by value and returns the future,
+    /// e.g. the `async fn`.
+    fn endpoint_async<S, Q, I, F, Fut, E>(&mut self, name: &'static str, e: E) -> &mut Self
+    where
+        Q: DeserializeOwned + 'static,
+        I: Serialize + 'static,
+        F: Fn(S, Q) -> Fut + 'static + Clone,
+        Fut: Future<Output = Result<I>> + 'static,
+        E: Into<TypedFn<S, Q, I, Async<Fut>, F>>,
+        Self::Handler: From<NamedFn<S, Q, I, Async<Fut>, F>>,
+    {
+        let named_fn = NamedFn {
+            name,
+            method: None,
+            inner: e.into(),
+        };
+        self.raw_handler(Self::Handler::from(named_fn))
+    }
+
+    /// Adds the asynchronous endpoint with the explicitly specified HTTP method.
+    fn endpoint_async_with<S, Q, I, F, Fut, E>(
+        &mut self,
+        name: &'static str,
+        method: Method,
+        e: E,
+    ) -> &mut Self
+    where
+        Q: DeserializeOwned + 'static,
+        I: Serialize + 'static,
+        F: Fn(S, Q) -> Fut + 'static + Clone,
+        Fut: Future<Output = Result<I>> + 'static,
+        E: Into<TypedFn<S, Q, I, Async<Fut>, F>>,
+        Self::Handler: From<NamedFn<S, Q, I, Async<Fut>, F>>,
+    {
+        let named_fn = NamedFn {
+            name,
+            method: Some(method),
             inner: e.into(),
         };
         self.raw_handler(Self::Handler::from(named_fn))
     }
 
+    /// Adds the `POST` endpoint which accepts a signed transaction of type `T`
+    /// and submits it to the node, responding with the transaction hash.
+    fn transaction<T>(&mut self, name: &'static str) -> &mut Self
+    where
+        T: Into<Box<dyn Transaction>> + DeserializeOwned + 'static,
+        Self::Handler: From<NamedFn<
+            ServiceApiContextMut,
+            T,
+            TransactionResponse,
+            Result<TransactionResponse>,
+            TransactionFn<T>,
+        >>,
+    {
+        self.endpoint_with(name, Method::POST, submit_transaction::<T> as TransactionFn<T>)
+    }
+
     fn raw_handler(&mut self, handler: Self::Handler) -> &mut Self;
+
+    /// Returns the handlers of the added endpoints.
+    fn finish(self) -> Vec<Self::Handler>;
 }
 
 #[derive(Default)]
-pub struct ServiceApiBuilder {
-    pub web_backend: actix_backend::BackendBuilder,
+pub struct ServiceApiBuilder<B = actix_backend::BackendBuilder> {
+    pub web_backend: B,
 }
 
-impl ServiceApiBuilder {
+impl<B: ServiceApiBackend> ServiceApiBuilder<B> {
     pub fn endpoint<S, Q, I, R, F, E>(&mut self, name: &'static str, e: E) -> &mut Self
     where
         Q: DeserializeOwned + 'static,
         I: Serialize + 'static,
         F: for<'r> Fn(&'r S, Q) -> R + 'static + Clone,
         E: Into<TypedFn<S, Q, I, R, F>>,
-        actix_backend::RequestHandler: From<NamedFn<S, Q, I, R, F>>,
+        B::Handler: From<NamedFn<S, Q, I, R, F>>,
     {
         self.web_backend.endpoint(name, e);
         self
     }
+
+    pub fn endpoint_with<S, Q, I, R, F, E>(
+        &mut self,
+        name: &'static str,
+        method: Method,
+        e: E,
+    ) -> &mut Self
+    where
+        Q: DeserializeOwned + 'static,
+        I: Serialize + 'static,
+        F: for<'r> Fn(&'r S, Q) -> R + 'static + Clone,
+        E: Into<TypedFn<S, Q, I, R, F>>,
+        B::Handler: From<NamedFn<S, Q, I, R, F>>,
+    {
+        self.web_backend.endpoint_with(name, method, e);
+        self
+    }
+
+    pub fn endpoint_async<S, Q, I, F, Fut, E>(&mut self, name: &'static str, e: E) -> &mut Self
+    where
+        Q: DeserializeOwned + 'static,
+        I: Serialize + 'static,
+        F: Fn(S, Q) -> Fut + 'static + Clone,
+        Fut: Future<Output = Result<I>> + 'static,
+        E: Into<TypedFn<S, Q, I, Async<Fut>, F>>,
+        B::Handler: From<NamedFn<S, Q, I, Async<Fut>, F>>,
+    {
+        self.web_backend.endpoint_async(name, e);
+        self
+    }
+
+    pub fn endpoint_async_with<S, Q, I, F, Fut, E>(
+        &mut self,
+        name: &'static str,
+        method: Method,
+        e: E,
+    ) -> &mut Self
+    where
+        Q: DeserializeOwned + 'static,
+        I: Serialize + 'static,
+        F: Fn(S, Q) -> Fut + 'static + Clone,
+        Fut: Future<Output = Result<I>> + 'static,
+        E: Into<TypedFn<S, Q, I, Async<Fut>, F>>,
+        B::Handler: From<NamedFn<S, Q, I, Async<Fut>, F>>,
+    {
+        self.web_backend.endpoint_async_with(name, method, e);
+        self
+    }
+
+    /// Adds the `POST` endpoint which accepts a signed transaction of type `T`
+    /// and submits it to the node, responding with the transaction hash.
+    pub fn transaction<T>(&mut self, name: &'static str) -> &mut Self
+    where
+        T: Into<Box<dyn Transaction>> + DeserializeOwned + 'static,
+        B::Handler: From<NamedFn<
+            ServiceApiContextMut,
+            T,
+            TransactionResponse,
+            Result<TransactionResponse>,
+            TransactionFn<T>,
+        >>,
+    {
+        self.web_backend.transaction::<T>(name);
+        self
+    }
 }
 
+/// Collects the endpoints of the service for the backend `B`, which is chosen
+/// by the `ApiAggregator`.
 #[derive(Default)]
-pub struct ServiceApiInitializer {
-    pub public_api_builder: ServiceApiBuilder,
+pub struct ServiceApiInitializer<B = actix_backend::BackendBuilder> {
+    pub public_api_builder: ServiceApiBuilder<B>,
+    pub private_api_builder: ServiceApiBuilder<B>,
+    state: StateMap,
 }
 
-impl ServiceApiInitializer {
-    pub fn public_api(&mut self) -> &mut ServiceApiBuilder {
+impl<B> ServiceApiInitializer<B> {
+    /// Registers the state, which is available to both public and private endpoints
+    /// of the service through `ServiceApiContext::state`. The state of the same type
+    /// is replaced.
+    pub fn state<T: Any + Send + Sync>(&mut self, state: T) -> &mut Self {
+        self.state.insert(TypeId::of::<T>(), Box::new(state));
+        self
+    }
+
+    pub(crate) fn take_state(&mut self) -> ServiceState {
+        ServiceState {
+            values: Arc::new(::std::mem::take(&mut self.state)),
+        }
+    }
+
+    pub fn public_api(&mut self) -> &mut ServiceApiBuilder<B> {
         &mut self.public_api_builder
     }
+
+    /// Api for the node administration, which should be available only to the node maintainers.
+    pub fn private_api(&mut self) -> &mut ServiceApiBuilder<B> {
+        &mut self.private_api_builder
+    }
 }
 
-pub trait Service {
-    fn initialize_api(&self, initializer: &mut ServiceApiInitializer);
+/// Service, which initializes its API for the backend `B`. The services, which are not
+/// tied to a backend, may implement the trait for any `B: ServiceApiBackend`.
+pub trait Service<B = actix_backend::BackendBuilder> {
+    /// Unique service name, which is used as a part of the service API prefix.
+    fn service_name(&self) -> &str;
+
+    fn initialize_api(&self, initializer: &mut ServiceApiInitializer<B>);
 }
@@ -0,0 +1,208 @@
+//! Helpers to test the service endpoints without the running node.
+
+use actix_http::Request;
+use actix_web::dev::{Service as HttpService, ServiceResponse};
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use actix_web::App;
+use chrono::Utc;
+use exonum::blockchain::{self, Blockchain, GenesisConfig, Schema, ValidatorKeys};
+use exonum::crypto::{self, Hash, PublicKey, SecretKey};
+use exonum::helpers::{Height, Round, ValidatorId};
+use exonum::messages::Precommit;
+use exonum::node::{ApiSender, ExternalMessage};
+use exonum::storage::{Database, MemoryDB};
+use futures01::sync::mpsc;
+use futures01::{future, Async, Future, Stream};
+use serde::de::DeserializeOwned;
+use warp::filters::BoxedFilter;
+use warp::reply::Response;
+
+use std::sync::Arc;
+
+use crate::actix_backend::{self, configure, RequestHandler};
+use crate::aggregator::ApiAggregator;
+use crate::error::Error;
+use crate::service::{Service, ServiceApiBackend, ServiceApiContextMut};
+use crate::{warp_backend, Result};
+
+/// Size of the buffer for the messages, which are sent by the endpoints to the node.
+const API_MESSAGES_BUFFER: usize = 64;
+
+/// Harness of the services, which serves their APIs with the backend `B`.
+pub struct TestHarness<B: ServiceApiBackend = actix_backend::BackendBuilder> {
+    context: ServiceApiContextMut,
+    aggregator: ApiAggregator<B>,
+    api_receiver: mpsc::Receiver<ExternalMessage>,
+    /// Consensus keys of the single validator of the initialized blockchain.
+    validator: Option<(PublicKey, SecretKey)>,
+}
+
+impl<B: ServiceApiBackend + Default> TestHarness<B> {
+    /// Creates the harness on top of the in-memory database.
+    pub fn new(services: &[Box<dyn Service<B>>]) -> Result<TestHarness<B>> {
+        TestHarness::with_database(MemoryDB::new(), services)
+    }
+
+    pub fn with_database<D: Into<Arc<dyn Database>>>(
+        database: D,
+        services: &[Box<dyn Service<B>>],
+    ) -> Result<TestHarness<B>> {
+        let (api_sender, api_receiver) = mpsc::channel(API_MESSAGES_BUFFER);
+        let context = ServiceApiContextMut::with_database(database, ApiSender::new(api_sender));
+        Ok(TestHarness {
+            context,
+            aggregator: ApiAggregator::new(services)?,
+            api_receiver,
+            validator: None,
+        })
+    }
+
+    /// Creates the harness on top of the in-memory blockchain with the given node services.
+    /// The blockchain is initialized with the genesis block of the single validator,
+    /// which signs the blocks created by `commit_block`.
+    pub fn with_blockchain(
+        blockchain_services: Vec<Box<dyn blockchain::Service>>,
+        services: &[Box<dyn Service<B>>],
+    ) -> Result<TestHarness<B>> {
+        let (consensus_key, consensus_secret_key) = crypto::gen_keypair();
+        let (service_key, service_secret_key) = crypto::gen_keypair();
+        let (api_sender, api_receiver) = mpsc::channel(API_MESSAGES_BUFFER);
+        let api_sender = ApiSender::new(api_sender);
+        let mut blockchain = Blockchain::new(
+            MemoryDB::new(),
+            blockchain_services,
+            service_key,
+            service_secret_key,
+            api_sender.clone(),
+        );
+        let validator_keys = ValidatorKeys {
+            consensus_key,
+            service_key,
+        };
+        blockchain
+            .initialize(GenesisConfig::new(vec![validator_keys].into_iter()))
+            .map_err(Error::internal)?;
+
+        Ok(TestHarness {
+            context: ServiceApiContextMut::new(blockchain, api_sender),
+            aggregator: ApiAggregator::new(services)?,
+            api_receiver,
+            validator: Some((consensus_key, consensus_secret_key)),
+        })
+    }
+
+    /// Creates the harness of the single service on top of the in-memory blockchain,
+    /// see `with_blockchain`.
+    pub fn with_service<S: Service<B> + 'static>(service: S) -> Result<TestHarness<B>> {
+        let services: Vec<Box<dyn Service<B>>> = vec![Box::new(service)];
+        TestHarness::with_blockchain(vec![], &services)
+    }
+
+    /// Runs the synchronous handlers of the actix backend on the blocking threads
+    /// of the test runtime, see `ServiceApiContextMut::with_blocking_handlers`.
+    pub fn with_blocking_handlers(mut self) -> TestHarness<B> {
+        self.context = self.context.with_blocking_handlers();
+        self
+    }
+
+    pub fn context(&self) -> &ServiceApiContextMut {
+        &self.context
+    }
+
+    /// Returns the consensus key of the validator, if the harness is created by
+    /// `with_blockchain`.
+    pub fn validator_key(&self) -> Option<&PublicKey> {
+        self.validator.as_ref().map(|(public_key, _)| public_key)
+    }
+
+    /// Commits the next block with the given transactions from the pool, the block is
+    /// signed by the validator of the blockchain created by `with_blockchain`.
+    pub fn commit_block(&mut self, txs: &[Hash]) -> Result<Height> {
+        let secret_key = match self.validator {
+            Some((_, ref secret_key)) => secret_key,
+            None => return Err(Error::internal("Blockchain is not initialized")),
+        };
+        let mut blockchain = self.context.blockchain.clone();
+        let height = Schema::new(&blockchain.snapshot()).height().next();
+        let (block_hash, patch) = blockchain.create_patch(ValidatorId(0), height, txs);
+        let precommit = Precommit::new(
+            ValidatorId(0),
+            height,
+            Round(1),
+            &crypto::hash(b"propose"),
+            &block_hash,
+            Utc::now(),
+            secret_key,
+        );
+        blockchain
+            .commit(&patch, block_hash, vec![precommit].iter())
+            .map_err(Error::internal)?;
+        Ok(height)
+    }
+
+    /// Returns the next message sent to the node by the endpoints, if any.
+    pub fn api_message(&mut self) -> Option<ExternalMessage> {
+        let receiver = &mut self.api_receiver;
+        match future::lazy(move || receiver.poll()).wait() {
+            Ok(Async::Ready(message)) => message,
+            _ => None,
+        }
+    }
+}
+
+impl TestHarness<actix_backend::BackendBuilder> {
+    /// Creates the service, which handles the requests to the public API
+    /// as the HTTP server would do.
+    pub async fn public_service(
+        &self,
+    ) -> impl HttpService<Request, Response = ServiceResponse, Error = actix_web::Error> {
+        service(self.context.clone(), self.aggregator.public_api()).await
+    }
+
+    pub async fn private_service(
+        &self,
+    ) -> impl HttpService<Request, Response = ServiceResponse, Error = actix_web::Error> {
+        service(self.context.clone(), self.aggregator.private_api()).await
+    }
+
+    /// Sends the `GET` request to the public API and returns the status and the body
+    /// of the response.
+    pub async fn request(&self, path: &str) -> (StatusCode, Vec<u8>) {
+        let service = self.public_service().await;
+        let request = TestRequest::get().uri(path).to_request();
+        let response = test::call_service(&service, request).await;
+        let status = response.status();
+        (status, test::read_body(response).await.to_vec())
+    }
+
+    /// Sends the `GET` request to the public API and parses the JSON body of the response.
+    ///
+    /// # Panics
+    ///
+    /// If the request fails or its body is not the JSON of `T`.
+    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> T {
+        let (status, body) = self.request(path).await;
+        assert_eq!(status, StatusCode::OK, "Request to `{}` has failed", path);
+        serde_json::from_slice(&body).unwrap()
+    }
+}
+
+impl TestHarness<warp_backend::BackendBuilder> {
+    /// Creates the warp filter, which handles the requests to the public API.
+    pub fn public_routes(&self) -> BoxedFilter<(Response,)> {
+        warp_backend::routes(self.context.clone(), self.aggregator.public_api())
+    }
+
+    pub fn private_routes(&self) -> BoxedFilter<(Response,)> {
+        warp_backend::routes(self.context.clone(), self.aggregator.private_api())
+    }
+}
+
+/// Creates the service, which handles the requests to the given APIs.
+pub async fn service(
+    context: ServiceApiContextMut,
+    apis: Vec<(String, Vec<RequestHandler>)>,
+) -> impl HttpService<Request, Response = ServiceResponse, Error = actix_web::Error> {
+    test::init_service(App::new().configure(move |config| configure(config, context, apis))).await
+}
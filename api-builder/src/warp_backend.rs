@@ -0,0 +1,339 @@
+use futures::future::{self, BoxFuture, FutureExt};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use warp::filters::BoxedFilter;
+use warp::http::header::{
+    HeaderMap, HeaderName, HeaderValue, CONTENT_LENGTH, CONTENT_TYPE, TRANSFER_ENCODING,
+};
+use warp::http::{Method, StatusCode};
+use warp::hyper::body::Bytes;
+use warp::path::FullPath;
+use warp::reply::Response;
+use warp::{Filter, Rejection, Reply};
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::io;
+use std::net::ToSocketAddrs;
+use std::sync::Arc;
+
+use crate::batch::BatchHandler;
+use crate::error::Error;
+use crate::handler::{self, Request};
+use crate::service::{EndpointMeta, ServiceApiBackend, ServiceApiContext, ServiceApiContextMut};
+use crate::{Async, NamedFn, Result};
+
+/// Maximum size of the request body, which is the default limit of the actix `Bytes` extractor.
+pub const MAX_BODY_SIZE: u64 = 256 * 1024;
+
+/// Future of the JSON response body.
+pub type ResponseFuture = BoxFuture<'static, Result<Vec<u8>>>;
+
+/// Future of the batch item response.
+pub type BatchFuture = BoxFuture<'static, Result<Value>>;
+
+pub type RawHandler = handler::RawHandler<ResponseFuture>;
+
+pub type RequestHandler = handler::RequestHandler<ResponseFuture, BatchFuture>;
+
+#[derive(Default)]
+pub struct BackendBuilder {
+    handlers: Vec<RequestHandler>,
+}
+
+impl ServiceApiBackend for BackendBuilder {
+    type Handler = RequestHandler;
+
+    fn raw_handler(&mut self, handler: Self::Handler) -> &mut Self {
+        self.handlers.push(handler);
+        self
+    }
+
+    fn finish(self) -> Vec<RequestHandler> {
+        self.handlers
+    }
+}
+
+impl BackendBuilder {
+    pub fn new() -> BackendBuilder {
+        BackendBuilder::default()
+    }
+}
+
+impl handler::Response for Response {
+    fn json(body: Vec<u8>) -> Self {
+        let mut response = Response::new(body.into());
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        response
+    }
+
+    fn error(error: &Error) -> Self {
+        let body = warp::reply::json(&error.to_public());
+        warp::reply::with_status(body, error.kind.status_code()).into_response()
+    }
+
+    fn status(&self) -> StatusCode {
+        Response::status(self)
+    }
+
+    fn insert_header(&mut self, name: HeaderName, value: HeaderValue) {
+        self.headers_mut().insert(name, value);
+    }
+}
+
+/// Creates the filter, which serves the handlers under the `api/<prefix>` paths,
+/// the `/_endpoints` listing of them and the `/batch` endpoint, which invokes them.
+pub fn routes(
+    context: ServiceApiContextMut,
+    apis: Vec<(String, Vec<RequestHandler>)>,
+) -> BoxedFilter<(Response,)> {
+    let endpoints = handler::endpoints(&apis);
+    let listing = warp::get()
+        .and(warp::path("_endpoints"))
+        .and(warp::path::end())
+        .map(move || warp::reply::json(&endpoints).into_response());
+
+    let batch_handlers = Arc::new(handler::batch_handlers(&apis));
+    let batch_context = context.clone();
+    let batch = warp::post()
+        .and(warp::path("batch"))
+        .and(warp::path::end())
+        .and(warp::header::headers_cloned())
+        .and(body())
+        .then(move |headers: HeaderMap, body: Bytes| {
+            let request = Request {
+                method: &Method::POST,
+                path: "/batch",
+                query: "",
+                headers: &headers,
+                body: &body,
+            };
+            handler::handle_batch::<Response, _>(
+                &request,
+                batch_context.clone(),
+                batch_handlers.clone(),
+            )
+        });
+
+    let handlers = apis
+        .into_iter()
+        .flat_map(|(prefix, handlers)| {
+            handlers.into_iter().map(move |handler| {
+                let path = format!("/api/{}/{}", prefix, handler.name);
+                ((handler.method, path), (handler.name, handler.inner))
+            })
+        })
+        .collect::<HashMap<_, _>>();
+    let handlers = Arc::new(handlers);
+
+    let api = warp::method()
+        .and(warp::path::full())
+        .and(raw_query())
+        .and(warp::header::headers_cloned())
+        .and(body())
+        .and_then(
+            move |method: Method,
+                  path: FullPath,
+                  query: String,
+                  headers: HeaderMap,
+                  body: Bytes| {
+                let endpoint = handlers.get(&(method.clone(), path.as_str().to_owned()));
+                let response = endpoint.map(|(name, inner)| {
+                    let request = Request {
+                        method: &method,
+                        path: path.as_str(),
+                        query: &query,
+                        headers: &headers,
+                        body: &body,
+                    };
+                    handler::handle::<Response, _>(name, &request, context.clone(), &**inner)
+                });
+                async move {
+                    let response = response.ok_or_else(warp::reject::not_found)?;
+                    Ok::<_, Rejection>(response.await)
+                }
+            },
+        );
+
+    listing.or(batch).unify().or(api).unify().boxed()
+}
+
+/// Binds the HTTP server with the given APIs, the returned future should be awaited
+/// within the tokio runtime.
+pub fn start_server(
+    listen_address: &str,
+    context: ServiceApiContextMut,
+    apis: Vec<(String, Vec<RequestHandler>)>,
+) -> io::Result<impl Future<Output = ()>> {
+    let address = listen_address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Empty listen address"))?;
+    let (_, server) = warp::serve(routes(context, apis))
+        .try_bind_ephemeral(address)
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    Ok(server)
+}
+
+/// Body of the request, which is limited to `MAX_BODY_SIZE` bytes. The requests without
+/// the `Content-Length` header are accepted only if they have no body, e.g. the `GET` ones.
+fn body() -> BoxedFilter<(Bytes,)> {
+    let empty = warp::header::headers_cloned().and_then(|headers: HeaderMap| async move {
+        if headers.contains_key(CONTENT_LENGTH) || headers.contains_key(TRANSFER_ENCODING) {
+            Err(warp::reject::not_found())
+        } else {
+            Ok(Bytes::new())
+        }
+    });
+    warp::body::content_length_limit(MAX_BODY_SIZE)
+        .and(warp::body::bytes())
+        .or(empty)
+        .unify()
+        .boxed()
+}
+
+/// Raw query string, which is empty if the request has no query.
+fn raw_query() -> BoxedFilter<(String,)> {
+    warp::query::raw()
+        .or(warp::any().map(String::new))
+        .unify()
+        .boxed()
+}
+
+/// Wraps the handler, which takes the mutable context and the extracted query,
+/// into the raw handler.
+fn create_raw_handler<Q, I, H, Fut>(method: Method, handler: H) -> Arc<RawHandler>
+where
+    Q: DeserializeOwned + 'static,
+    I: Serialize + 'static,
+    H: Fn(ServiceApiContextMut, Q) -> Fut + 'static + Send + Sync,
+    Fut: Future<Output = Result<I>> + 'static + Send,
+{
+    Arc::new(
+        move |context: ServiceApiContextMut, query: &str, body: &[u8]| {
+            handler::call_handler(&handler, context, &method, query, body).boxed()
+        },
+    )
+}
+
+/// Wraps the handler into the handler of the batch items, which takes the query as JSON.
+fn create_batch_handler<S, Q, I, H, Fut>(
+    handler: H,
+) -> Arc<dyn Fn(S, Value) -> BatchFuture + Send + Sync>
+where
+    S: 'static,
+    Q: DeserializeOwned + 'static,
+    I: Serialize + 'static,
+    H: Fn(S, Q) -> Fut + 'static + Send + Sync,
+    Fut: Future<Output = Result<I>> + 'static + Send,
+{
+    Arc::new(move |context: S, query: Value| {
+        handler::call_batch_handler(&handler, context, query).boxed()
+    })
+}
+
+/// The synchronous handlers are run in place on the event loop, unlike the ones
+/// of the actix backend with `ServiceApiContextMut::with_blocking_handlers`.
+impl<Q, I, F> From<NamedFn<ServiceApiContext, Q, I, Result<I>, F>> for RequestHandler
+where
+    F: for<'r> Fn(&'r ServiceApiContext, Q) -> Result<I> + 'static + Send + Sync,
+    Q: DeserializeOwned + 'static,
+    I: Serialize + 'static + Send,
+{
+    fn from(f: NamedFn<ServiceApiContext, Q, I, Result<I>, F>) -> Self {
+        let handler = Arc::new(f.inner.f);
+        let method = f.method.unwrap_or(Method::GET);
+        let batch_handler = handler.clone();
+        let batch = move |context: ServiceApiContext, query: Q| {
+            future::ready(batch_handler(&context, query))
+        };
+        let index =
+            move |context: ServiceApiContextMut, query: Q| future::ready(handler(&context, query));
+
+        RequestHandler {
+            name: f.name,
+            method: method.clone(),
+            meta: EndpointMeta::new::<Q, I>(false, false),
+            inner: create_raw_handler(method, index),
+            batch: BatchHandler::Immutable(create_batch_handler(batch)),
+        }
+    }
+}
+
+impl<Q, I, F> From<NamedFn<ServiceApiContextMut, Q, I, Result<I>, F>> for RequestHandler
+where
+    F: for<'r> Fn(&'r ServiceApiContextMut, Q) -> Result<I> + 'static + Send + Sync,
+    Q: DeserializeOwned + 'static,
+    I: Serialize + 'static + Send,
+{
+    fn from(f: NamedFn<ServiceApiContextMut, Q, I, Result<I>, F>) -> Self {
+        let handler = Arc::new(f.inner.f);
+        let method = f.method.unwrap_or(Method::POST);
+        let batch_handler = handler.clone();
+        let batch = move |context: ServiceApiContextMut, query: Q| {
+            future::ready(batch_handler(&context, query))
+        };
+        let index =
+            move |context: ServiceApiContextMut, query: Q| future::ready(handler(&context, query));
+
+        RequestHandler {
+            name: f.name,
+            method: method.clone(),
+            meta: EndpointMeta::new::<Q, I>(true, false),
+            inner: create_raw_handler(method, index),
+            batch: BatchHandler::Mutable(create_batch_handler(batch)),
+        }
+    }
+}
+
+impl<Q, I, F, Fut> From<NamedFn<ServiceApiContext, Q, I, Async<Fut>, F>> for RequestHandler
+where
+    F: Fn(ServiceApiContext, Q) -> Fut + 'static + Send + Sync,
+    Fut: Future<Output = Result<I>> + 'static + Send,
+    Q: DeserializeOwned + 'static,
+    I: Serialize + 'static,
+{
+    fn from(f: NamedFn<ServiceApiContext, Q, I, Async<Fut>, F>) -> Self {
+        let handler = Arc::new(f.inner.f);
+        let method = f.method.unwrap_or(Method::GET);
+        let batch_handler = handler.clone();
+        let index = move |context: ServiceApiContextMut, query: Q| handler(context.inner, query);
+
+        RequestHandler {
+            name: f.name,
+            method: method.clone(),
+            meta: EndpointMeta::new::<Q, I>(false, true),
+            inner: create_raw_handler(method, index),
+            batch: BatchHandler::Immutable(create_batch_handler(
+                move |context: ServiceApiContext, query: Q| batch_handler(context, query),
+            )),
+        }
+    }
+}
+
+impl<Q, I, F, Fut> From<NamedFn<ServiceApiContextMut, Q, I, Async<Fut>, F>> for RequestHandler
+where
+    F: Fn(ServiceApiContextMut, Q) -> Fut + 'static + Send + Sync,
+    Fut: Future<Output = Result<I>> + 'static + Send,
+    Q: DeserializeOwned + 'static,
+    I: Serialize + 'static,
+{
+    fn from(f: NamedFn<ServiceApiContextMut, Q, I, Async<Fut>, F>) -> Self {
+        let handler = Arc::new(f.inner.f);
+        let method = f.method.unwrap_or(Method::POST);
+        let batch_handler = handler.clone();
+
+        RequestHandler {
+            name: f.name,
+            method: method.clone(),
+            meta: EndpointMeta::new::<Q, I>(true, true),
+            inner: create_raw_handler(method, move |context, query: Q| handler(context, query)),
+            batch: BatchHandler::Mutable(create_batch_handler(
+                move |context: ServiceApiContextMut, query: Q| batch_handler(context, query),
+            )),
+        }
+    }
+}
@@ -0,0 +1,293 @@
+#[macro_use]
+extern crate serde_json;
+
+use actix_web::http::{Method, StatusCode};
+use actix_web::test::{self, TestRequest};
+
+use std::sync::atomic::Ordering;
+
+use api_builder::access_log::{self, MAX_REQUEST_ID_LEN};
+use api_builder::aggregator::ApiAggregator;
+use api_builder::batch::{BatchItem, BatchResult, MAX_BATCH_ITEMS};
+use api_builder::error::{Error, ErrorKind, INTERNAL_ERROR_MESSAGE};
+use api_builder::service::{Service, ServiceApiContext, ServiceApiContextMut,
+                           ServiceApiInitializer};
+use api_builder::testing::TestHarness;
+
+use crate::common::{ActixClient, Counter, Sum, TestService};
+
+mod common;
+
+#[actix_web::test]
+async fn test_public_api() {
+    let harness: TestHarness = TestHarness::with_service(TestService).unwrap();
+    let client = ActixClient(harness.public_service().await);
+    common::check_public_api(&client).await;
+}
+
+#[actix_web::test]
+async fn test_private_api() {
+    let harness: TestHarness = TestHarness::with_service(TestService).unwrap();
+    let client = ActixClient(harness.private_service().await);
+    common::check_private_api(&client).await;
+}
+
+#[actix_web::test]
+async fn test_error_responses() {
+    let harness: TestHarness = TestHarness::with_service(TestService).unwrap();
+    let client = ActixClient(harness.public_service().await);
+    common::check_error_responses(&client).await;
+}
+
+#[actix_web::test]
+async fn test_endpoints_listing() {
+    let harness: TestHarness = TestHarness::with_service(TestService).unwrap();
+    let client = ActixClient(harness.public_service().await);
+    common::check_endpoints_listing(&client).await;
+}
+
+#[actix_web::test]
+async fn test_batch() {
+    let harness: TestHarness = TestHarness::with_service(TestService).unwrap();
+    let client = ActixClient(harness.public_service().await);
+    common::check_batch(&client).await;
+}
+
+#[actix_web::test]
+async fn test_request_id() {
+    let harness: TestHarness = TestHarness::with_service(TestService).unwrap();
+    let client = ActixClient(harness.private_service().await);
+    common::check_request_id(&client).await;
+}
+
+#[actix_web::test]
+async fn test_signed_requests() {
+    let harness: TestHarness = TestHarness::with_service(TestService).unwrap();
+    let client = ActixClient(harness.private_service().await);
+    common::check_signed_requests(&client).await;
+}
+
+#[actix_web::test]
+async fn test_signed_batch() {
+    let harness: TestHarness = TestHarness::with_service(TestService).unwrap();
+    let client = ActixClient(harness.private_service().await);
+    common::check_signed_batch(&client).await;
+}
+
+struct CounterService(&'static str);
+
+impl Service for CounterService {
+    fn service_name(&self) -> &str {
+        self.0
+    }
+
+    fn initialize_api(&self, initializer: &mut ServiceApiInitializer) {
+        initializer.state(Counter::default());
+        initializer
+            .public_api()
+            .endpoint(
+                "increment",
+                |context: &ServiceApiContextMut, _: ()| -> Result<u64, Error> {
+                    let counter = context.state::<Counter>()?;
+                    Ok(counter.0.fetch_add(1, Ordering::SeqCst) + 1)
+                },
+            )
+            .endpoint(
+                "missing",
+                |context: &ServiceApiContext, _: ()| -> Result<u64, Error> {
+                    context.state::<u64>().map(|value| *value)
+                },
+            );
+        initializer
+            .private_api()
+            .endpoint_async("count", count);
+    }
+}
+
+async fn count(context: ServiceApiContext, _: ()) -> Result<u64, Error> {
+    Ok(context.state::<Counter>()?.0.load(Ordering::SeqCst))
+}
+
+#[actix_web::test]
+async fn test_batch_mutable_endpoints() {
+    let services: Vec<Box<dyn Service>> = vec![Box::new(CounterService("counter"))];
+    let harness = TestHarness::new(&services).unwrap();
+    let service = harness.public_service().await;
+
+    let increment = BatchItem {
+        endpoint: "/api/services/counter/increment".to_owned(),
+        method: None,
+        query: json!(null),
+    };
+    let request = TestRequest::post()
+        .uri("/batch")
+        .set_json(vec![increment.clone(), increment])
+        .to_request();
+    let results: Vec<BatchResult> = test::call_and_read_body_json(&service, request).await;
+    assert_eq!(
+        results,
+        vec![BatchResult::Ok(json!(1)), BatchResult::Ok(json!(2))]
+    );
+}
+
+#[test]
+fn test_invalid_request_id() {
+    assert_eq!(
+        access_log::request_id(Some("client-id_1.2")),
+        "client-id_1.2"
+    );
+
+    let long_id = "a".repeat(MAX_REQUEST_ID_LEN + 1);
+    for id in &["", "forged status=200", "id\nrequest_id=other", &long_id] {
+        let request_id = access_log::request_id(Some(id));
+        assert_ne!(request_id, *id);
+        assert!(!request_id.is_empty());
+    }
+}
+
+#[actix_web::test]
+async fn test_service_state() {
+    let services: Vec<Box<dyn Service>> = vec![
+        Box::new(CounterService("first")),
+        Box::new(CounterService("second")),
+    ];
+    let harness = TestHarness::new(&services).unwrap();
+    let public_service = harness.public_service().await;
+    let private_service = harness.private_service().await;
+
+    for expected in 1..3 {
+        let request = TestRequest::post()
+            .uri("/api/services/first/increment")
+            .set_json(())
+            .to_request();
+        let count: u64 = test::call_and_read_body_json(&public_service, request).await;
+        assert_eq!(count, expected);
+    }
+
+    // The state is shared by the public and private endpoints of the same service only.
+    let request = TestRequest::get()
+        .uri("/api/services/first/count")
+        .to_request();
+    let count: u64 = test::call_and_read_body_json(&private_service, request).await;
+    assert_eq!(count, 2);
+    let request = TestRequest::get()
+        .uri("/api/services/second/count")
+        .to_request();
+    let count: u64 = test::call_and_read_body_json(&private_service, request).await;
+    assert_eq!(count, 0);
+
+    let request = TestRequest::get()
+        .uri("/api/services/first/missing")
+        .to_request();
+    let response = test::call_service(&public_service, request).await;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    // The type name of the missing state is not disclosed to the client.
+    let error: Error = test::read_body_json(response).await;
+    assert_eq!(error, Error::internal(INTERNAL_ERROR_MESSAGE));
+}
+
+#[test]
+fn test_duplicate_service() {
+    let services: Vec<Box<dyn Service>> = vec![Box::new(TestService), Box::new(TestService)];
+    assert!(ApiAggregator::new(&services).is_err());
+}
+
+struct MethodsService {
+    duplicate: bool,
+}
+
+impl Service for MethodsService {
+    fn service_name(&self) -> &str {
+        "methods"
+    }
+
+    fn initialize_api(&self, initializer: &mut ServiceApiInitializer) {
+        let sum = |_: &ServiceApiContext, sum: Sum| -> Result<u64, Error> { Ok(sum.a + sum.b) };
+        let product = |_: &ServiceApiContext, sum: Sum| -> Result<u64, Error> { Ok(sum.a * sum.b) };
+        let method = if self.duplicate {
+            Method::GET
+        } else {
+            Method::POST
+        };
+        initializer
+            .public_api()
+            .endpoint("sum", sum)
+            .endpoint_with("sum", method, product);
+    }
+}
+
+#[test]
+fn test_duplicate_endpoint() {
+    let mut aggregator: ApiAggregator = ApiAggregator::default();
+    assert!(aggregator
+        .add_service(&MethodsService { duplicate: false })
+        .is_ok());
+
+    let mut aggregator: ApiAggregator = ApiAggregator::default();
+    assert!(aggregator
+        .add_service(&MethodsService { duplicate: true })
+        .is_err());
+}
+
+struct SafeMethodService;
+
+impl Service for SafeMethodService {
+    fn service_name(&self) -> &str {
+        "safe"
+    }
+
+    fn initialize_api(&self, initializer: &mut ServiceApiInitializer) {
+        initializer.public_api().endpoint_with(
+            "reset",
+            Method::GET,
+            |_: &ServiceApiContextMut, _: ()| -> Result<(), Error> { Ok(()) },
+        );
+    }
+}
+
+#[test]
+fn test_mutable_endpoint_with_safe_method() {
+    let mut aggregator: ApiAggregator = ApiAggregator::default();
+    assert!(aggregator.add_service(&SafeMethodService).is_err());
+}
+
+#[actix_web::test]
+async fn test_batch_methods() {
+    let services: Vec<Box<dyn Service>> = vec![Box::new(MethodsService { duplicate: false })];
+    let harness = TestHarness::new(&services).unwrap();
+    let service = harness.public_service().await;
+
+    let item = |method: Option<&str>| BatchItem {
+        endpoint: "/api/services/methods/sum".to_owned(),
+        method: method.map(str::to_owned),
+        query: json!({ "a": 2, "b": 3 }),
+    };
+    let items = vec![
+        item(Some("GET")),
+        item(Some("post")),
+        item(None),
+        item(Some("PUT")),
+    ];
+    let request = TestRequest::post()
+        .uri("/batch")
+        .set_json(&items)
+        .to_request();
+    let results: Vec<BatchResult> = test::call_and_read_body_json(&service, request).await;
+    assert_eq!(results[0], BatchResult::Ok(json!(5)));
+    assert_eq!(results[1], BatchResult::Ok(json!(6)));
+    match (&results[2], &results[3]) {
+        (BatchResult::Error(ambiguous), BatchResult::Error(missing)) => {
+            assert_eq!(ambiguous.kind, ErrorKind::BadRequest);
+            assert_eq!(missing.kind, ErrorKind::NotFound);
+        }
+        _ => panic!("Batch items should fail"),
+    }
+
+    let items = vec![item(Some("GET")); MAX_BATCH_ITEMS + 1];
+    let request = TestRequest::post()
+        .uri("/batch")
+        .set_json(&items)
+        .to_request();
+    let response = test::call_service(&service, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
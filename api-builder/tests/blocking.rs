@@ -0,0 +1,142 @@
+#[macro_use]
+extern crate serde_json;
+
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+use serde_derive::Serialize;
+use serde_json::Value;
+
+use std::marker::PhantomData;
+use std::thread;
+
+use api_builder::batch::{BatchItem, BatchResult};
+use api_builder::error::Error;
+use api_builder::service::{Service, ServiceApiContext, ServiceApiContextMut,
+                           ServiceApiInitializer};
+use api_builder::testing::TestHarness;
+
+/// Returns the ID of the current thread, which is distinct for the blocking threads
+/// and the event loop.
+fn thread_id() -> String {
+    format!("{:?}", thread::current().id())
+}
+
+/// ID of the thread, which is not `Send`, so it is serialized on the thread of the handler.
+#[derive(Serialize)]
+struct LocalId {
+    id: String,
+    #[serde(skip)]
+    _local: PhantomData<*const ()>,
+}
+
+async fn thread_async(_: ServiceApiContext, _: ()) -> Result<String, Error> {
+    Ok(thread_id())
+}
+
+struct ThreadService;
+
+impl Service for ThreadService {
+    fn service_name(&self) -> &str {
+        "thread"
+    }
+
+    fn initialize_api(&self, initializer: &mut ServiceApiInitializer) {
+        initializer
+            .public_api()
+            .endpoint(
+                "id",
+                |_: &ServiceApiContext, _: ()| -> Result<String, Error> { Ok(thread_id()) },
+            )
+            .endpoint(
+                "id_mut",
+                |_: &ServiceApiContextMut, _: ()| -> Result<String, Error> { Ok(thread_id()) },
+            )
+            .endpoint(
+                "local_id",
+                |_: &ServiceApiContext, _: ()| -> Result<LocalId, Error> {
+                    Ok(LocalId {
+                        id: thread_id(),
+                        _local: PhantomData,
+                    })
+                },
+            )
+            .endpoint(
+                "panic",
+                |_: &ServiceApiContext, _: ()| -> Result<u64, Error> {
+                    panic!("Handler failure")
+                },
+            )
+            .endpoint_async("id_async", thread_async);
+    }
+}
+
+#[actix_web::test]
+async fn test_blocking_handlers() {
+    let harness = TestHarness::with_service(ThreadService)
+        .unwrap()
+        .with_blocking_handlers();
+    let service = harness.public_service().await;
+
+    // The asynchronous handlers are run on the event loop.
+    let request = TestRequest::get()
+        .uri("/api/services/thread/id_async")
+        .to_request();
+    let event_loop: String = test::call_and_read_body_json(&service, request).await;
+
+    let request = TestRequest::get()
+        .uri("/api/services/thread/id")
+        .to_request();
+    let id: String = test::call_and_read_body_json(&service, request).await;
+    assert_ne!(id, event_loop);
+
+    let request = TestRequest::post()
+        .uri("/api/services/thread/id_mut")
+        .set_json(&())
+        .to_request();
+    let id: String = test::call_and_read_body_json(&service, request).await;
+    assert_ne!(id, event_loop);
+
+    let request = TestRequest::get()
+        .uri("/api/services/thread/local_id")
+        .to_request();
+    let local_id: Value = test::call_and_read_body_json(&service, request).await;
+    assert_ne!(local_id["id"], json!(event_loop));
+
+    let request = TestRequest::post()
+        .uri("/batch")
+        .set_json(vec![BatchItem {
+            endpoint: "/api/services/thread/id_mut".to_owned(),
+            method: None,
+            query: json!(null),
+        }])
+        .to_request();
+    let results: Vec<BatchResult> = test::call_and_read_body_json(&service, request).await;
+    match results[0] {
+        BatchResult::Ok(ref id) => assert_ne!(*id, json!(event_loop)),
+        _ => panic!("Batch item should succeed"),
+    }
+
+    // The panic of the handler is reported as the internal error.
+    let request = TestRequest::get()
+        .uri("/api/services/thread/panic")
+        .to_request();
+    let response = test::call_service(&service, request).await;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[actix_web::test]
+async fn test_handlers_in_place() {
+    let harness = TestHarness::with_service(ThreadService).unwrap();
+    let service = harness.public_service().await;
+
+    let request = TestRequest::get()
+        .uri("/api/services/thread/id_async")
+        .to_request();
+    let event_loop: String = test::call_and_read_body_json(&service, request).await;
+
+    let request = TestRequest::get()
+        .uri("/api/services/thread/id")
+        .to_request();
+    let id: String = test::call_and_read_body_json(&service, request).await;
+    assert_eq!(id, event_loop);
+}
@@ -0,0 +1,165 @@
+use actix_web::{App, HttpServer};
+use exonum::crypto::{self, PublicKey};
+use exonum::node::ApiSender;
+use exonum::storage::MemoryDB;
+use serde_derive::{Deserialize, Serialize};
+
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use api_builder::actix_backend::{configure, RequestHandler};
+use api_builder::aggregator::ApiAggregator;
+use api_builder::client::ApiClient;
+use api_builder::error::{Error, ErrorKind};
+use api_builder::service::{
+    Service, ServiceApiContext, ServiceApiContextMut, ServiceApiInitializer,
+};
+use api_builder::service_api;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Sum {
+    a: u64,
+    b: u64,
+}
+
+service_api! {
+    /// Public API of the test service.
+    trait CalculatorApi for ServiceApiContext {
+        client CalculatorClient;
+
+        fn sum(&self, query: Sum) -> u64;
+        fn ping(&self, query: ()) -> String;
+        /// Always fails.
+        fn find(&self, query: Sum) -> Sum;
+    }
+}
+
+service_api! {
+    /// Private API of the test service.
+    trait CounterApi for ServiceApiContextMut {
+        client CounterClient;
+
+        fn increment(&self, query: u64) -> u64;
+        /// Returns the public key of the client, which has signed the request.
+        fn whoami(&self, query: ()) -> Option<PublicKey>;
+    }
+}
+
+#[derive(Default)]
+struct Counter(AtomicU64);
+
+impl CalculatorApi for ServiceApiContext {
+    fn sum(&self, query: Sum) -> Result<u64, Error> {
+        Ok(query.a + query.b)
+    }
+
+    fn ping(&self, _: ()) -> Result<String, Error> {
+        Ok("pong".to_owned())
+    }
+
+    fn find(&self, query: Sum) -> Result<Sum, Error> {
+        Err(Error::not_found(format!("Nothing found for {}", query.a)))
+    }
+}
+
+impl CounterApi for ServiceApiContextMut {
+    fn increment(&self, by: u64) -> Result<u64, Error> {
+        let counter = self.state::<Counter>()?;
+        Ok(counter.0.fetch_add(by, Ordering::SeqCst) + by)
+    }
+
+    fn whoami(&self, _: ()) -> Result<Option<PublicKey>, Error> {
+        Ok(self.caller().cloned())
+    }
+}
+
+struct TestService;
+
+impl Service for TestService {
+    fn service_name(&self) -> &str {
+        "test"
+    }
+
+    fn initialize_api(&self, initializer: &mut ServiceApiInitializer) {
+        initializer.state(Counter::default());
+        <ServiceApiContext as CalculatorApi>::wire(initializer.public_api());
+        <ServiceApiContextMut as CounterApi>::wire(initializer.private_api());
+    }
+}
+
+/// Starts the actix server with the given APIs on the free port and returns its URL.
+fn start_server(context: ServiceApiContextMut, apis: Vec<(String, Vec<RequestHandler>)>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = HttpServer::new(move || {
+        let context = context.clone();
+        let apis = apis.clone();
+        App::new().configure(move |config| configure(config, context, apis))
+    })
+    .workers(1)
+    .listen(listener)
+    .unwrap()
+    .run();
+    actix_web::rt::spawn(server);
+    format!("http://{}", address)
+}
+
+/// Starts the public and private servers of the test service.
+fn start_servers() -> (ApiClient, ApiClient) {
+    let services: Vec<Box<dyn Service>> = vec![Box::new(TestService)];
+    let aggregator = ApiAggregator::new(&services).unwrap();
+    let api_sender = ApiSender::new(futures01::sync::mpsc::channel(1).0);
+    let context = ServiceApiContextMut::with_database(MemoryDB::new(), api_sender);
+
+    let public_url = start_server(context.clone(), aggregator.public_api());
+    let private_url = start_server(context, aggregator.private_api());
+    (ApiClient::new(public_url), ApiClient::new(private_url))
+}
+
+#[actix_web::test]
+async fn test_public_client() {
+    let (public_api, _) = start_servers();
+    let client = CalculatorClient::new(public_api, "test");
+
+    assert_eq!(client.sum(&Sum { a: 2, b: 3 }).await.unwrap(), 5);
+    assert_eq!(client.ping(&()).await.unwrap(), "pong");
+
+    let error = client.find(&Sum { a: 1, b: 2 }).await.unwrap_err();
+    assert_eq!(error, Error::not_found("Nothing found for 1"));
+}
+
+#[actix_web::test]
+async fn test_private_client() {
+    let (public_api, private_api) = start_servers();
+    let client = CounterClient::new(private_api, "test");
+
+    assert_eq!(client.increment(&2).await.unwrap(), 2);
+    assert_eq!(client.increment(&3).await.unwrap(), 5);
+
+    // Private endpoints are not mounted on the public server.
+    let client = CounterClient::new(public_api, "test");
+    let error = client.increment(&1).await.unwrap_err();
+    assert_eq!(error.kind, ErrorKind::NotFound);
+}
+
+#[actix_web::test]
+async fn test_unknown_service() {
+    let (public_api, _) = start_servers();
+    let client = CalculatorClient::new(public_api, "unknown");
+
+    let error = client.sum(&Sum { a: 2, b: 3 }).await.unwrap_err();
+    assert_eq!(error.kind, ErrorKind::NotFound);
+}
+
+#[actix_web::test]
+async fn test_signed_client() {
+    let (_, private_api) = start_servers();
+    let (public_key, secret_key) = crypto::gen_keypair();
+
+    let client = CounterClient::new(private_api.clone(), "test");
+    assert_eq!(client.whoami(&()).await.unwrap(), None);
+
+    let client = CounterClient::new(private_api.with_keys(public_key, secret_key), "test");
+    assert_eq!(client.whoami(&()).await.unwrap(), Some(public_key));
+    assert_eq!(client.increment(&1).await.unwrap(), 1);
+}
@@ -0,0 +1,545 @@
+//! Test service and the checks of its API, which are shared by the tests of the backends.
+//! The backends serve the same service and pass the same checks through their `Client`.
+
+// Each test crate uses its own part of the module.
+#![allow(dead_code)]
+
+use actix_http::Request as ActixRequest;
+use actix_web::dev::{Service as HttpService, ServiceResponse};
+use actix_web::test::{self as actix_test, TestRequest};
+use exonum::crypto::{self, PublicKey, SecretKey};
+use futures::future::{self, BoxFuture, FutureExt, LocalBoxFuture};
+use http::header::HeaderMap;
+use http::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_derive::{Deserialize, Serialize};
+use warp::filters::BoxedFilter;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use api_builder::access_log::REQUEST_ID_HEADER;
+use api_builder::auth::{
+    self, MAX_TIMESTAMP_SKEW, PUBLIC_KEY_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER,
+};
+use api_builder::batch::{BatchItem, BatchResult};
+use api_builder::error::{Error, ErrorKind};
+use api_builder::service::{EndpointInfo, Service, ServiceApiBackend, ServiceApiContext,
+                           ServiceApiContextMut, ServiceApiInitializer};
+use api_builder::{Async, NamedFn};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Sum {
+    pub a: u64,
+    pub b: u64,
+}
+
+#[derive(Default)]
+pub struct Counter(pub AtomicU64);
+
+// The handlers are the plain functions, so that the bounds of the backend handlers
+// can name their types.
+type SyncHandler<S, Q, I> = fn(&S, Q) -> Result<I, Error>;
+type SyncFn<S, Q, I> = NamedFn<S, Q, I, Result<I, Error>, SyncHandler<S, Q, I>>;
+type SumFuture = BoxFuture<'static, Result<u64, Error>>;
+type AsyncSumFn =
+    NamedFn<ServiceApiContext, Sum, u64, Async<SumFuture>, fn(ServiceApiContext, Sum) -> SumFuture>;
+
+fn sum(_: &ServiceApiContext, sum: Sum) -> Result<u64, Error> {
+    Ok(sum.a + sum.b)
+}
+
+fn find(_: &ServiceApiContext, sum: Sum) -> Result<u64, Error> {
+    Err(Error::not_found(format!("Nothing found for {}", sum.a)))
+}
+
+fn height(context: &ServiceApiContextMut, _: ()) -> Result<u64, Error> {
+    Ok(context.blockchain.last_block().height().0)
+}
+
+fn increment(context: &ServiceApiContextMut, _: ()) -> Result<u64, Error> {
+    let counter = context.state::<Counter>()?;
+    Ok(counter.0.fetch_add(1, Ordering::SeqCst) + 1)
+}
+
+fn sum_async(_: ServiceApiContext, sum: Sum) -> SumFuture {
+    future::ready(Ok(sum.a + sum.b)).boxed()
+}
+
+fn ping(_: &ServiceApiContext, _: ()) -> Result<String, Error> {
+    Ok("pong".to_owned())
+}
+
+fn echo(_: &ServiceApiContextMut, sum: Sum) -> Result<u64, Error> {
+    Ok(sum.a)
+}
+
+fn request_id(context: &ServiceApiContext, _: ()) -> Result<Option<String>, Error> {
+    Ok(context.request_id().map(str::to_owned))
+}
+
+fn caller(context: &ServiceApiContext, _: ()) -> Result<Option<PublicKey>, Error> {
+    Ok(context.caller().cloned())
+}
+
+fn signed(context: &ServiceApiContextMut, _: Sum) -> Result<PublicKey, Error> {
+    context.signed_caller().map(|key| *key)
+}
+
+pub struct TestService;
+
+impl<B> Service<B> for TestService
+where
+    B: ServiceApiBackend,
+    B::Handler: From<SyncFn<ServiceApiContext, Sum, u64>>
+        + From<SyncFn<ServiceApiContextMut, (), u64>>
+        + From<AsyncSumFn>
+        + From<SyncFn<ServiceApiContext, (), String>>
+        + From<SyncFn<ServiceApiContextMut, Sum, u64>>
+        + From<SyncFn<ServiceApiContext, (), Option<String>>>
+        + From<SyncFn<ServiceApiContext, (), Option<PublicKey>>>
+        + From<SyncFn<ServiceApiContextMut, Sum, PublicKey>>,
+{
+    fn service_name(&self) -> &str {
+        "test"
+    }
+
+    fn initialize_api(&self, initializer: &mut ServiceApiInitializer<B>) {
+        initializer.state(Counter::default());
+        initializer
+            .public_api()
+            .endpoint("sum", sum as SyncHandler<_, _, _>)
+            .endpoint_with("sum_post", Method::POST, sum as SyncHandler<_, _, _>)
+            .endpoint("find", find as SyncHandler<_, _, _>)
+            .endpoint("height", height as SyncHandler<_, _, _>)
+            .endpoint("increment", increment as SyncHandler<_, _, _>)
+            .endpoint_async("sum_async", sum_async as fn(_, _) -> _);
+        initializer
+            .private_api()
+            .endpoint("ping", ping as SyncHandler<_, _, _>)
+            .endpoint_with("echo", Method::DELETE, echo as SyncHandler<_, _, _>)
+            .endpoint("request_id", request_id as SyncHandler<_, _, _>)
+            .endpoint("caller", caller as SyncHandler<_, _, _>)
+            .endpoint("signed", signed as SyncHandler<_, _, _>);
+    }
+}
+
+/// Request to the API of the backend. The requests without the body are sent
+/// without the `Content-Length` header as well.
+pub struct Request {
+    pub method: Method,
+    pub path: String,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: Option<Vec<u8>>,
+}
+
+impl Request {
+    pub fn new(method: Method, path: &str) -> Request {
+        Request {
+            method,
+            path: path.to_owned(),
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    pub fn get(path: &str) -> Request {
+        Request::new(Method::GET, path)
+    }
+
+    pub fn post(path: &str) -> Request {
+        Request::new(Method::POST, path)
+    }
+
+    pub fn header(mut self, name: &'static str, value: impl Into<String>) -> Request {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Request {
+        self.body = Some(body.into());
+        self
+    }
+
+    pub fn json<T: Serialize>(self, value: &T) -> Request {
+        self.body(serde_json::to_vec(value).unwrap())
+    }
+
+    /// Signs the request by the given keys at the given time. The signature covers
+    /// the query string and the body of the request.
+    pub fn signed(self, timestamp: u64, keys: &(PublicKey, SecretKey)) -> Request {
+        let body = self.body.clone().unwrap_or_default();
+        let (path, query) = match self.path.find('?') {
+            Some(index) => (&self.path[..index], &self.path[index + 1..]),
+            None => (self.path.as_str(), ""),
+        };
+        let headers = sign_request(&self.method, path, query, timestamp, &body, &keys.1);
+        let mut request = self.header(PUBLIC_KEY_HEADER, keys.0.to_hex());
+        request.headers.extend(headers);
+        request
+    }
+}
+
+/// Signs the request at the given time, returns the headers of the signed request
+/// except the public key of the caller.
+pub fn sign_request(
+    method: &Method,
+    path: &str,
+    query: &str,
+    timestamp: u64,
+    body: &[u8],
+    secret_key: &SecretKey,
+) -> Vec<(&'static str, String)> {
+    let timestamp = timestamp.to_string();
+    let message = auth::signed_message(method, path, &timestamp, query, body);
+    vec![
+        (SIGNATURE_HEADER, auth::sign(&message, secret_key).to_hex()),
+        (TIMESTAMP_HEADER, timestamp),
+    ]
+}
+
+pub struct Response {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// Parses the JSON body of the response.
+    pub fn json<T: DeserializeOwned>(&self) -> T {
+        serde_json::from_slice(&self.body).unwrap()
+    }
+
+    pub fn header(&self, name: &str) -> &str {
+        self.headers[name].to_str().unwrap()
+    }
+}
+
+/// Client of the API served by the backend.
+pub trait Client {
+    fn send(&self, request: Request) -> LocalBoxFuture<'_, Response>;
+}
+
+/// Client of the actix service, which handles the requests as the HTTP server would do.
+pub struct ActixClient<S>(pub S);
+
+impl<S> Client for ActixClient<S>
+where
+    S: HttpService<ActixRequest, Response = ServiceResponse, Error = actix_web::Error>,
+{
+    fn send(&self, request: Request) -> LocalBoxFuture<'_, Response> {
+        let mut test_request = TestRequest::default()
+            .method(request.method)
+            .uri(&request.path);
+        for header in request.headers {
+            test_request = test_request.insert_header(header);
+        }
+        if let Some(body) = request.body {
+            test_request = test_request.set_payload(body);
+        }
+        async move {
+            let response = actix_test::call_service(&self.0, test_request.to_request()).await;
+            let status = response.status();
+            let headers = response
+                .headers()
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect();
+            let body = actix_test::read_body(response).await.to_vec();
+            Response {
+                status,
+                headers,
+                body,
+            }
+        }
+        .boxed_local()
+    }
+}
+
+/// Client of the warp routes.
+pub struct WarpClient(pub BoxedFilter<(warp::reply::Response,)>);
+
+impl Client for WarpClient {
+    fn send(&self, request: Request) -> LocalBoxFuture<'_, Response> {
+        let mut test_request = warp::test::request()
+            .method(request.method.as_str())
+            .path(&request.path);
+        for (name, value) in request.headers {
+            test_request = test_request.header(name, value);
+        }
+        if let Some(body) = request.body {
+            test_request = test_request.body(body);
+        }
+        async move {
+            let response = test_request.reply(&self.0).await;
+            Response {
+                status: response.status(),
+                headers: response.headers().clone(),
+                body: response.body().to_vec(),
+            }
+        }
+        .boxed_local()
+    }
+}
+
+/// Sends the request and parses the JSON body of the successful response.
+async fn call<C: Client, T: DeserializeOwned>(client: &C, request: Request) -> T {
+    let path = request.path.clone();
+    let response = client.send(request).await;
+    assert_eq!(
+        response.status,
+        StatusCode::OK,
+        "Request to `{}` has failed",
+        path
+    );
+    response.json()
+}
+
+fn batch_item(endpoint: &str, query: serde_json::Value) -> BatchItem {
+    BatchItem {
+        endpoint: endpoint.to_owned(),
+        method: None,
+        query,
+    }
+}
+
+pub async fn check_public_api<C: Client>(public: &C) {
+    // The queries of the `GET` requests are sent in the query string without the body.
+    let sum: u64 = call(public, Request::get("/api/services/test/sum?a=2&b=3")).await;
+    assert_eq!(sum, 5);
+    let sum: u64 = call(public, Request::get("/api/services/test/sum_async?a=1&b=6")).await;
+    assert_eq!(sum, 7);
+    let request = Request::post("/api/services/test/sum_post").json(&Sum { a: 4, b: 5 });
+    let sum: u64 = call(public, request).await;
+    assert_eq!(sum, 9);
+
+    let request = Request::post("/api/services/test/height").json(&());
+    let height: u64 = call(public, request).await;
+    assert_eq!(height, 0);
+    for expected in 1..3 {
+        let request = Request::post("/api/services/test/increment").json(&());
+        let count: u64 = call(public, request).await;
+        assert_eq!(count, expected);
+    }
+
+    // Private endpoints are not mounted on the public server.
+    let response = public.send(Request::get("/api/services/test/ping")).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+pub async fn check_private_api<C: Client>(private: &C) {
+    let pong: String = call(private, Request::get("/api/services/test/ping")).await;
+    assert_eq!(pong, "pong");
+
+    let request = Request::new(Method::DELETE, "/api/services/test/echo?a=7&b=0");
+    let a: u64 = call(private, request).await;
+    assert_eq!(a, 7);
+}
+
+pub async fn check_error_responses<C: Client>(public: &C) {
+    let response = public
+        .send(Request::get("/api/services/test/find?a=1&b=2"))
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(
+        response.json::<Error>(),
+        Error::not_found("Nothing found for 1")
+    );
+
+    for path in &[
+        "/api/services/test/sum?a=2",
+        "/api/services/test/sum_async?a=1",
+    ] {
+        let response = public.send(Request::get(path)).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+        assert_eq!(response.json::<Error>().kind, ErrorKind::BadRequest);
+    }
+
+    let request = Request::post("/api/services/test/height").body("not a json");
+    let response = public.send(request).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+pub async fn check_endpoints_listing<C: Client>(public: &C) {
+    let endpoints: Vec<EndpointInfo> = call(public, Request::get("/_endpoints")).await;
+    let names = endpoints
+        .iter()
+        .map(|info| (info.name.as_str(), info.method.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec![
+            ("sum", "GET"),
+            ("sum_post", "POST"),
+            ("find", "GET"),
+            ("height", "POST"),
+            ("increment", "POST"),
+            ("sum_async", "GET"),
+        ]
+    );
+
+    let sum = &endpoints[0];
+    assert_eq!(sum.path, "/api/services/test/sum");
+    assert!(!sum.mutable);
+    assert!(!sum.asynchronous);
+    assert!(sum.query_type.ends_with("Sum"));
+    assert_eq!(sum.response_type, "u64");
+
+    let height = &endpoints[3];
+    assert!(height.mutable);
+    assert_eq!(height.query_type, "()");
+
+    let sum_async = &endpoints[5];
+    assert!(!sum_async.mutable);
+    assert!(sum_async.asynchronous);
+}
+
+pub async fn check_batch<C: Client>(public: &C) {
+    let items = vec![
+        batch_item("/api/services/test/sum", json!({ "a": 1, "b": 2 })),
+        batch_item("/api/services/test/sum_async", json!({ "a": 3, "b": 4 })),
+        batch_item("/api/services/test/increment", json!(null)),
+        batch_item("/api/services/test/increment", json!(null)),
+        batch_item("/api/services/test/find", json!({ "a": 1, "b": 2 })),
+        batch_item("/api/services/test/sum", json!({ "a": 1 })),
+        // Private endpoints are not available through the public batch.
+        batch_item("/api/services/test/ping", json!(null)),
+    ];
+    let results: Vec<BatchResult> = call(public, Request::post("/batch").json(&items)).await;
+
+    assert_eq!(results.len(), 7);
+    assert_eq!(
+        results[..4].to_vec(),
+        vec![
+            BatchResult::Ok(json!(3)),
+            BatchResult::Ok(json!(7)),
+            BatchResult::Ok(json!(1)),
+            BatchResult::Ok(json!(2)),
+        ]
+    );
+    assert_eq!(
+        results[4],
+        BatchResult::Error(Error::not_found("Nothing found for 1"))
+    );
+    match (&results[5], &results[6]) {
+        (BatchResult::Error(incorrect), BatchResult::Error(missing)) => {
+            assert_eq!(incorrect.kind, ErrorKind::BadRequest);
+            assert_eq!(missing.kind, ErrorKind::NotFound);
+        }
+        _ => panic!("Batch items should fail"),
+    }
+
+    let response = public
+        .send(Request::post("/batch").body("not a json"))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+pub async fn check_request_id<C: Client>(private: &C) {
+    let request =
+        Request::get("/api/services/test/request_id").header(REQUEST_ID_HEADER, "client-id");
+    let response = private.send(request).await;
+    assert_eq!(response.header(REQUEST_ID_HEADER), "client-id");
+    let request_id: Option<String> = response.json();
+    assert_eq!(request_id.unwrap(), "client-id");
+
+    // The ID is generated if the client has not provided it.
+    let response = private
+        .send(Request::get("/api/services/test/request_id"))
+        .await;
+    let header = response.header(REQUEST_ID_HEADER);
+    assert!(!header.is_empty());
+    let request_id: Option<String> = response.json();
+    assert_eq!(request_id.unwrap(), header);
+
+    // Failed requests carry the ID as well.
+    let request = Request::new(Method::DELETE, "/api/services/test/echo?a=7")
+        .header(REQUEST_ID_HEADER, "failed");
+    let response = private.send(request).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.header(REQUEST_ID_HEADER), "failed");
+}
+
+pub async fn check_signed_requests<C: Client>(private: &C) {
+    let keys = crypto::gen_keypair();
+    let (public_key, secret_key) = &keys;
+    let now = auth::timestamp();
+
+    let caller: Option<PublicKey> = call(private, Request::get("/api/services/test/caller")).await;
+    assert_eq!(caller, None);
+
+    // The query string of the `GET` requests is signed, which is empty for the unit query.
+    let request = Request::get("/api/services/test/caller").signed(now, &keys);
+    let caller: Option<PublicKey> = call(private, request).await;
+    assert_eq!(caller, Some(*public_key));
+
+    let path = "/api/services/test/signed";
+    let body = serde_json::to_vec(&Sum { a: 1, b: 2 }).unwrap();
+    let request = Request::post(path).body(body.clone()).signed(now, &keys);
+    let caller: PublicKey = call(private, request).await;
+    assert_eq!(caller, *public_key);
+
+    let other_body = serde_json::to_vec(&Sum { a: 1, b: 3 }).unwrap();
+    let stale = now - MAX_TIMESTAMP_SKEW - 60;
+    // The requests carry the body of the sum, but sign the given path, time and body.
+    let signed = |path: &str, timestamp: u64, signed_body: &[u8]| {
+        let mut request = Request::post("/api/services/test/signed")
+            .header(PUBLIC_KEY_HEADER, public_key.to_hex())
+            .body(body.clone());
+        let headers = sign_request(&Method::POST, path, "", timestamp, signed_body, secret_key);
+        request.headers.extend(headers);
+        request
+    };
+    let signature = sign_request(&Method::POST, path, "", now, &body, secret_key);
+    let requests = vec![
+        // The endpoint accepts the signed requests only.
+        Request::post(path).body(body.clone()),
+        // The signature of the other body.
+        signed(path, now, &other_body),
+        // The signature of the request to the other path is replayed.
+        signed("/api/services/test/other", now, &body),
+        // The timestamp is outside of the allowed window.
+        signed(path, stale, &body),
+        // The timestamp is missing.
+        Request::post(path)
+            .header(PUBLIC_KEY_HEADER, public_key.to_hex())
+            .header(signature[0].0, signature[0].1.clone())
+            .body(body.clone()),
+        // The signature is missing.
+        Request::post(path)
+            .header(PUBLIC_KEY_HEADER, public_key.to_hex())
+            .header(TIMESTAMP_HEADER, now.to_string())
+            .body(body.clone()),
+        Request::post(path)
+            .header(PUBLIC_KEY_HEADER, public_key.to_hex())
+            .header(SIGNATURE_HEADER, "malformed")
+            .header(TIMESTAMP_HEADER, now.to_string())
+            .body(body.clone()),
+    ];
+    for request in requests {
+        let response = private.send(request).await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+        assert_eq!(response.json::<Error>().kind, ErrorKind::Unauthorized);
+    }
+}
+
+pub async fn check_signed_batch<C: Client>(private: &C) {
+    let keys = crypto::gen_keypair();
+    let items = vec![batch_item(
+        "/api/services/test/signed",
+        json!({ "a": 1, "b": 2 }),
+    )];
+
+    let request = Request::post("/batch")
+        .json(&items)
+        .signed(auth::timestamp(), &keys);
+    let results: Vec<BatchResult> = call(private, request).await;
+    assert_eq!(results, vec![BatchResult::Ok(json!(keys.0))]);
+
+    // The items of the anonymous batch are anonymous as well.
+    let results: Vec<BatchResult> = call(private, Request::post("/batch").json(&items)).await;
+    match results[0] {
+        BatchResult::Error(ref error) => assert_eq!(error.kind, ErrorKind::Unauthorized),
+        _ => panic!("Anonymous batch item should fail"),
+    }
+}
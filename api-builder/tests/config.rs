@@ -0,0 +1,157 @@
+use tempdir::TempDir;
+
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+
+use api_builder::config::{NodeConfig, ServiceKeys};
+
+#[test]
+fn test_default_config() {
+    let config = NodeConfig::from_args(vec!["api-builder"]).unwrap();
+    assert_eq!(config, NodeConfig::default());
+    assert_eq!(config.public_api_address, "127.0.0.1:8080");
+    assert_eq!(config.private_api_address, "127.0.0.1:8081");
+}
+
+#[test]
+fn test_config_args() {
+    let config = NodeConfig::from_args(vec![
+        "api-builder",
+        "--public-api-address",
+        "127.0.0.1:9000",
+        "--private-api-address",
+        "127.0.0.1:9001",
+        "-d",
+        "/tmp/node",
+        "-k",
+        "/tmp/node.keys.toml",
+        "--log-level",
+        "debug",
+        "--blocking-threads",
+        "2",
+    ])
+    .unwrap();
+    assert_eq!(
+        config,
+        NodeConfig {
+            public_api_address: "127.0.0.1:9000".to_owned(),
+            private_api_address: "127.0.0.1:9001".to_owned(),
+            database_path: Some(PathBuf::from("/tmp/node")),
+            key_file: Some(PathBuf::from("/tmp/node.keys.toml")),
+            log_level: Some("debug".to_owned()),
+            blocking_threads: Some(2),
+        }
+    );
+
+    assert!(NodeConfig::from_args(vec!["api-builder", "--unknown"]).is_err());
+    assert!(NodeConfig::from_args(vec!["api-builder", "--blocking-threads", "many"]).is_err());
+}
+
+#[test]
+fn test_config_file() {
+    let dir = TempDir::new("api-builder-config").unwrap();
+    let path = dir.path().join("node.toml");
+    fs::write(
+        &path,
+        "public_api_address = \"127.0.0.1:9000\"\nlog_level = \"info\"\n",
+    )
+    .unwrap();
+
+    // The omitted fields have the default values.
+    let config = NodeConfig::load(&path).unwrap();
+    assert_eq!(config.public_api_address, "127.0.0.1:9000");
+    assert_eq!(config.private_api_address, "127.0.0.1:8081");
+    assert_eq!(config.log_level, Some("info".to_owned()));
+
+    // The arguments take precedence over the file.
+    let config = NodeConfig::from_args(vec![
+        "api-builder".as_ref(),
+        "--config".as_ref(),
+        path.as_os_str(),
+        "--log-level".as_ref(),
+        "warn".as_ref(),
+    ])
+    .unwrap();
+    assert_eq!(config.public_api_address, "127.0.0.1:9000");
+    assert_eq!(config.log_level, Some("warn".to_owned()));
+
+    let saved_path = dir.path().join("saved.toml");
+    config.save(&saved_path).unwrap();
+    assert_eq!(NodeConfig::load(&saved_path).unwrap(), config);
+}
+
+#[test]
+fn test_blocking_threads() {
+    let config = NodeConfig {
+        blocking_threads: Some(3),
+        ..NodeConfig::default()
+    };
+    assert_eq!(config.max_blocking_threads().unwrap(), 3);
+    assert!(NodeConfig::default().max_blocking_threads().unwrap() > 0);
+
+    let config = NodeConfig {
+        blocking_threads: Some(0),
+        ..NodeConfig::default()
+    };
+    assert!(config.max_blocking_threads().is_err());
+}
+
+#[test]
+fn test_key_file() {
+    let dir = TempDir::new("api-builder-keys").unwrap();
+    let path = dir.path().join("node.keys.toml");
+
+    let keys = ServiceKeys::load_or_generate(&path).unwrap();
+    assert!(path.exists());
+    assert_eq!(ServiceKeys::load_or_generate(&path).unwrap(), keys);
+
+    let config = NodeConfig {
+        key_file: Some(path.clone()),
+        ..NodeConfig::default()
+    };
+    assert_eq!(config.service_keys().unwrap(), keys);
+
+    // The keys of the different pairs are rejected.
+    let other = ServiceKeys::generate();
+    let mismatched = ServiceKeys {
+        public_key: other.public_key,
+        secret_key: keys.secret_key,
+    };
+    fs::write(&path, toml::to_string(&mismatched).unwrap()).unwrap();
+    assert!(ServiceKeys::load_or_generate(&path).is_err());
+}
+
+#[test]
+fn test_concurrent_key_file() {
+    let dir = TempDir::new("api-builder-keys").unwrap();
+    let path = dir.path().join("node.keys.toml");
+
+    let starters = (0..8)
+        .map(|_| {
+            let path = path.clone();
+            thread::spawn(move || ServiceKeys::load_or_generate(&path).unwrap())
+        })
+        .collect::<Vec<_>>();
+    let keys = starters
+        .into_iter()
+        .map(|starter| starter.join().unwrap())
+        .collect::<Vec<_>>();
+    assert!(keys.iter().all(|other| *other == keys[0]));
+
+    // The temporary files are removed.
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_key_file_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = TempDir::new("api-builder-keys").unwrap();
+    let path = dir.path().join("node.keys.toml");
+
+    ServiceKeys::load_or_generate(&path).unwrap();
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+}
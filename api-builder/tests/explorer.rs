@@ -0,0 +1,221 @@
+#[macro_use]
+extern crate exonum;
+
+use actix_web::http::StatusCode;
+
+use api_builder::explorer::{BlockInfo, BlocksRange, ExplorerService, MAX_BLOCKS_PER_REQUEST};
+use api_builder::service;
+use api_builder::testing::TestHarness;
+
+use exonum::blockchain::{ExecutionResult, Schema, Transaction, TransactionSet};
+use exonum::crypto::{self, CryptoHash, Hash, PublicKey};
+use exonum::encoding::Error as EncodingError;
+use exonum::explorer::TransactionInfo;
+use exonum::helpers::Height;
+use exonum::messages::{Message, RawTransaction};
+use exonum::storage::{Fork, Snapshot};
+
+const SERVICE_ID: u16 = 1;
+
+transactions! {
+    TestTransactions {
+        const SERVICE_ID = SERVICE_ID;
+
+        struct Increment {
+            author: &PublicKey,
+            seed: u64,
+        }
+    }
+}
+
+impl Transaction for Increment {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.author())
+    }
+
+    fn execute(&self, _: &mut Fork) -> ExecutionResult {
+        Ok(())
+    }
+}
+
+struct TestService;
+
+impl exonum::blockchain::Service for TestService {
+    fn service_id(&self) -> u16 {
+        SERVICE_ID
+    }
+
+    fn service_name(&self) -> &str {
+        "test"
+    }
+
+    fn state_hash(&self, _: &dyn Snapshot) -> Vec<Hash> {
+        Vec::new()
+    }
+
+    fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, EncodingError> {
+        TestTransactions::tx_from_raw(raw).map(Into::into)
+    }
+}
+
+struct Testkit {
+    harness: TestHarness,
+    committed: Increment,
+    pending: Increment,
+}
+
+fn add_into_pool(harness: &TestHarness, transaction: &Increment) {
+    let mut blockchain = harness.context().blockchain.clone();
+    let mut fork = blockchain.fork();
+    Schema::new(&mut fork).add_transaction_into_pool(transaction.raw().clone());
+    blockchain.merge(fork.into_patch()).unwrap();
+}
+
+/// Creates the blockchain with the committed transaction in the block at height 1
+/// and the other transaction in the pool.
+fn create_testkit() -> Testkit {
+    let services: Vec<Box<dyn service::Service>> = vec![Box::new(ExplorerService)];
+    let mut harness = TestHarness::with_blockchain(vec![Box::new(TestService)], &services).unwrap();
+
+    let (author, author_secret_key) = crypto::gen_keypair();
+    let committed = Increment::new(&author, 1, &author_secret_key);
+    let pending = Increment::new(&author, 2, &author_secret_key);
+
+    add_into_pool(&harness, &committed);
+    harness.commit_block(&[committed.hash()]).unwrap();
+    add_into_pool(&harness, &pending);
+
+    Testkit {
+        harness,
+        committed,
+        pending,
+    }
+}
+
+#[actix_web::test]
+async fn test_height() {
+    let testkit = create_testkit();
+
+    let height: Height = testkit.harness.get("/api/services/explorer/height").await;
+    assert_eq!(height, Height(1));
+}
+
+#[actix_web::test]
+async fn test_block() {
+    let testkit = create_testkit();
+
+    let info: BlockInfo = testkit
+        .harness
+        .get("/api/services/explorer/block?height=1")
+        .await;
+    assert_eq!(info.block.height(), Height(1));
+    assert_eq!(info.block.tx_count(), 1);
+    assert_eq!(info.txs, vec![testkit.committed.hash()]);
+    assert_eq!(info.precommits.len(), 1);
+    assert_eq!(info.precommits[0].block_hash(), &info.block.hash());
+
+    let (status, _) = testkit
+        .request("/api/services/explorer/block?height=2")
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn test_blocks() {
+    let testkit = create_testkit();
+
+    let range: BlocksRange = testkit
+        .harness
+        .get("/api/services/explorer/blocks?count=10")
+        .await;
+    assert_eq!(range.range, Height(0)..Height(2));
+    let heights = range
+        .blocks
+        .iter()
+        .map(|block| block.height())
+        .collect::<Vec<_>>();
+    assert_eq!(heights, vec![Height(1), Height(0)]);
+
+    let range: BlocksRange = testkit
+        .get("/api/services/explorer/blocks?count=1&latest=0")
+        .await;
+    assert_eq!(range.range, Height(0)..Height(1));
+    assert_eq!(range.blocks[0].height(), Height(0));
+
+    let range: BlocksRange = testkit
+        .get("/api/services/explorer/blocks?count=10&skip_empty_blocks=true")
+        .await;
+    assert_eq!(range.blocks.len(), 1);
+    assert_eq!(range.blocks[0].height(), Height(1));
+}
+
+#[actix_web::test]
+async fn test_blocks_incorrect_query() {
+    let testkit = create_testkit();
+
+    let path = format!(
+        "/api/services/explorer/blocks?count={}",
+        MAX_BLOCKS_PER_REQUEST + 1
+    );
+    let (status, _) = testkit.harness.request(&path).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = testkit
+        .request("/api/services/explorer/blocks?count=0")
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = testkit
+        .request("/api/services/explorer/blocks?count=1&latest=5")
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn test_transaction() {
+    let testkit = create_testkit();
+
+    let path = format!(
+        "/api/services/explorer/transaction?hash={}",
+        testkit.committed.hash().to_hex()
+    );
+    let info: TransactionInfo<Increment> = testkit.harness.get(&path).await;
+    match info {
+        TransactionInfo::Committed(ref tx) => {
+            assert_eq!(tx.location().block_height(), Height(1));
+            assert!(tx.status().is_ok());
+        }
+        _ => panic!("Transaction should be committed"),
+    }
+    assert_eq!(info.content(), &testkit.committed);
+
+    let path = format!(
+        "/api/services/explorer/transaction?hash={}",
+        testkit.pending.hash().to_hex()
+    );
+    let info: TransactionInfo<Increment> = testkit.harness.get(&path).await;
+    assert!(info.is_in_pool());
+    assert_eq!(info.content(), &testkit.pending);
+
+    let path = format!(
+        "/api/services/explorer/transaction?hash={}",
+        crypto::hash(b"unknown").to_hex()
+    );
+    let (status, _) = testkit.harness.request(&path).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn test_explorer_without_genesis_block() {
+    let services: Vec<Box<dyn service::Service>> = vec![Box::new(ExplorerService)];
+    let harness = TestHarness::new(&services).unwrap();
+
+    for path in &[
+        "/api/services/explorer/height",
+        "/api/services/explorer/block?height=0",
+        "/api/services/explorer/blocks?count=10",
+    ] {
+        let (status, _) = harness.request(path).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
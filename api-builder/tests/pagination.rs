@@ -0,0 +1,140 @@
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+
+use api_builder::error::{Error, ErrorKind};
+use api_builder::pagination::{self, Page, PageQuery, MAX_PAGE_LIMIT};
+use api_builder::service::{Service, ServiceApiContext, ServiceApiInitializer};
+use api_builder::testing::TestHarness;
+
+use exonum::storage::{Database, ListIndex, MapIndex, MemoryDB};
+
+const LIST: &str = "pagination.list";
+const MAP: &str = "pagination.map";
+
+/// Creates the database with the list of `0..len` numbers and the map of their squares.
+fn create_database(len: u64) -> MemoryDB {
+    let db = MemoryDB::new();
+    let mut fork = db.fork();
+    {
+        let mut list = ListIndex::new(LIST, &mut fork);
+        list.extend(0..len);
+        let mut map = MapIndex::new(MAP, &mut fork);
+        for i in 0..len {
+            map.put(&i, i * i);
+        }
+    }
+    db.merge(fork.into_patch()).unwrap();
+    db
+}
+
+fn page_query<C>(cursor: Option<C>, limit: usize) -> PageQuery<C> {
+    PageQuery {
+        cursor,
+        limit: Some(limit),
+    }
+}
+
+#[test]
+fn test_list_pages() {
+    let db = create_database(5);
+    let snapshot = db.snapshot();
+    let list: ListIndex<_, u64> = ListIndex::new(LIST, &snapshot);
+
+    let page = pagination::list_page(&list, &page_query(None, 2)).unwrap();
+    assert_eq!(page.items, vec![0, 1]);
+    assert_eq!(page.next, Some(2));
+
+    let page = pagination::list_page(&list, &page_query(page.next, 2)).unwrap();
+    assert_eq!(page.items, vec![2, 3]);
+    let page = pagination::list_page(&list, &page_query(page.next, 2)).unwrap();
+    assert_eq!(page.items, vec![4]);
+    assert_eq!(page.next, None);
+
+    // The page, which covers the list end, is the last one.
+    let page = pagination::list_page(&list, &page_query(Some(3), 2)).unwrap();
+    assert_eq!(page.items, vec![3, 4]);
+    assert_eq!(page.next, None);
+
+    let page = pagination::list_page(&list, &page_query(Some(10), 2)).unwrap();
+    assert!(page.items.is_empty());
+    assert_eq!(page.next, None);
+
+    let page = pagination::list_page(&list, &PageQuery::default()).unwrap();
+    assert_eq!(page.items.len(), 5);
+}
+
+#[test]
+fn test_map_pages() {
+    let db = create_database(5);
+    let snapshot = db.snapshot();
+    let map: MapIndex<_, u64, u64> = MapIndex::new(MAP, &snapshot);
+
+    let page = pagination::map_page(&map, &page_query(None, 3)).unwrap();
+    assert_eq!(page.items, vec![(0, 0), (1, 1), (2, 4)]);
+    assert_eq!(page.next, Some(3));
+
+    let page = pagination::map_page(&map, &page_query(page.next, 3)).unwrap();
+    assert_eq!(page.items, vec![(3, 9), (4, 16)]);
+    assert_eq!(page.next, None);
+
+    let page = pagination::map_page(&map, &page_query(Some(2), 3)).unwrap();
+    assert_eq!(page.items, vec![(2, 4), (3, 9), (4, 16)]);
+    assert_eq!(page.next, None);
+}
+
+#[test]
+fn test_page_limit() {
+    let db = create_database(1);
+    let snapshot = db.snapshot();
+    let list: ListIndex<_, u64> = ListIndex::new(LIST, &snapshot);
+
+    for limit in &[0, MAX_PAGE_LIMIT + 1] {
+        let error = pagination::list_page(&list, &page_query(None, *limit)).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::BadRequest);
+    }
+}
+
+struct ListService;
+
+impl Service for ListService {
+    fn service_name(&self) -> &str {
+        "list"
+    }
+
+    fn initialize_api(&self, initializer: &mut ServiceApiInitializer) {
+        initializer.public_api().endpoint(
+            "items",
+            |context: &ServiceApiContext, query: PageQuery| -> Result<Page<u64>, Error> {
+                let snapshot = context.blockchain.snapshot();
+                pagination::list_page(&ListIndex::new(LIST, &snapshot), &query)
+            },
+        );
+    }
+}
+
+#[actix_web::test]
+async fn test_list_endpoint() {
+    let services: Vec<Box<dyn Service>> = vec![Box::new(ListService)];
+    let harness = TestHarness::with_database(create_database(5), &services).unwrap();
+    let service = harness.public_service().await;
+
+    let request = TestRequest::get()
+        .uri("/api/services/list/items?limit=3")
+        .to_request();
+    let page: Page<u64> = test::call_and_read_body_json(&service, request).await;
+    assert_eq!(page.items, vec![0, 1, 2]);
+    assert_eq!(page.next, Some(3));
+
+    let request = TestRequest::get()
+        .uri("/api/services/list/items?cursor=3&limit=3")
+        .to_request();
+    let page: Page<u64> = test::call_and_read_body_json(&service, request).await;
+    assert_eq!(page.items, vec![3, 4]);
+    assert_eq!(page.next, None);
+
+    let request = TestRequest::get()
+        .uri("/api/services/list/items?limit=0")
+        .to_request();
+    let response = test::call_service(&service, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,194 @@
+use actix_web::http::StatusCode;
+use serde_derive::{Deserialize, Serialize};
+
+use api_builder::error::{Error, ErrorKind};
+use api_builder::proof::{ListStateProof, MapStateProof};
+use api_builder::service::{self, ServiceApiContext, ServiceApiInitializer};
+use api_builder::testing::TestHarness;
+
+use exonum::blockchain::Transaction;
+use exonum::crypto::{self, Hash, PublicKey};
+use exonum::encoding::Error as EncodingError;
+use exonum::messages::RawTransaction;
+use exonum::storage::{ProofListIndex, ProofMapIndex, Snapshot};
+
+const SERVICE_ID: u16 = 1;
+const BALANCES: &str = "proof.balances";
+const HISTORY: &str = "proof.history";
+
+struct ProofService;
+
+impl exonum::blockchain::Service for ProofService {
+    fn service_id(&self) -> u16 {
+        SERVICE_ID
+    }
+
+    fn service_name(&self) -> &str {
+        "proof"
+    }
+
+    fn state_hash(&self, snapshot: &dyn Snapshot) -> Vec<Hash> {
+        vec![
+            ProofMapIndex::<_, PublicKey, u64>::new(BALANCES, snapshot).merkle_root(),
+            ProofListIndex::<_, Hash>::new(HISTORY, snapshot).merkle_root(),
+        ]
+    }
+
+    fn tx_from_raw(&self, raw: RawTransaction) -> Result<Box<dyn Transaction>, EncodingError> {
+        Err(EncodingError::IncorrectMessageType {
+            message_type: raw.message_type(),
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BalanceQuery {
+    key: PublicKey,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct HistoryQuery {
+    index: u64,
+}
+
+struct ProofApi;
+
+impl service::Service for ProofApi {
+    fn service_name(&self) -> &str {
+        "proof"
+    }
+
+    fn initialize_api(&self, initializer: &mut ServiceApiInitializer) {
+        initializer
+            .public_api()
+            .endpoint(
+                "balance",
+                |context: &ServiceApiContext,
+                 query: BalanceQuery|
+                 -> Result<MapStateProof<PublicKey, u64>, Error> {
+                    context.map_proof(SERVICE_ID, 0, BALANCES, query.key)
+                },
+            )
+            .endpoint(
+                "history",
+                |context: &ServiceApiContext,
+                 query: HistoryQuery|
+                 -> Result<ListStateProof<Hash>, Error> {
+                    context.list_proof(SERVICE_ID, 1, HISTORY, query.index)
+                },
+            );
+    }
+}
+
+struct Testkit {
+    harness: TestHarness,
+    wallet_key: PublicKey,
+}
+
+impl Testkit {
+    fn validator_key(&self) -> PublicKey {
+        *self.harness.validator_key().unwrap()
+    }
+}
+
+fn create_testkit() -> Testkit {
+    let services: Vec<Box<dyn service::Service>> = vec![Box::new(ProofApi)];
+    let mut harness =
+        TestHarness::with_blockchain(vec![Box::new(ProofService)], &services).unwrap();
+
+    // Emulates the execution of the service transactions.
+    let (wallet_key, _) = crypto::gen_keypair();
+    let mut blockchain = harness.context().blockchain.clone();
+    let mut fork = blockchain.fork();
+    {
+        ProofMapIndex::new(BALANCES, &mut fork).put(&wallet_key, 100_u64);
+        let mut history = ProofListIndex::new(HISTORY, &mut fork);
+        history.push(crypto::hash(b"first"));
+        history.push(crypto::hash(b"second"));
+    }
+    blockchain.merge(fork.into_patch()).unwrap();
+    harness.commit_block(&[]).unwrap();
+
+    Testkit {
+        harness,
+        wallet_key,
+    }
+}
+
+#[actix_web::test]
+async fn test_map_proof() {
+    let testkit = create_testkit();
+    let path = format!(
+        "/api/services/proof/balance?key={}",
+        testkit.wallet_key.to_hex()
+    );
+
+    let proof: MapStateProof<PublicKey, u64> = testkit.harness.get(&path).await;
+    let balance = proof
+        .verify(&[testkit.validator_key()], SERVICE_ID, 0)
+        .unwrap();
+    assert_eq!(balance, Some(&100));
+    // The proof is useless for the other table.
+    assert!(proof
+        .verify(&[testkit.validator_key()], SERVICE_ID, 1)
+        .is_err());
+}
+
+#[actix_web::test]
+async fn test_missing_key_proof() {
+    let testkit = create_testkit();
+    let (missing_key, _) = crypto::gen_keypair();
+    let path = format!("/api/services/proof/balance?key={}", missing_key.to_hex());
+
+    let proof: MapStateProof<PublicKey, u64> = testkit.harness.get(&path).await;
+    let balance = proof
+        .verify(&[testkit.validator_key()], SERVICE_ID, 0)
+        .unwrap();
+    assert_eq!(balance, None);
+}
+
+#[actix_web::test]
+async fn test_list_proof() {
+    let testkit = create_testkit();
+
+    let proof: ListStateProof<Hash> = testkit
+        .harness
+        .get("/api/services/proof/history?index=1")
+        .await;
+    let elements = proof
+        .verify(&[testkit.validator_key()], SERVICE_ID, 1)
+        .unwrap();
+    assert_eq!(elements, vec![(1, &crypto::hash(b"second"))]);
+}
+
+#[actix_web::test]
+async fn test_list_proof_out_of_range() {
+    let testkit = create_testkit();
+
+    let (status, body) = testkit
+        .harness
+        .request("/api/services/proof/history?index=2")
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let error: Error = serde_json::from_slice(&body).unwrap();
+    assert_eq!(error.kind, ErrorKind::NotFound);
+}
+
+#[actix_web::test]
+async fn test_proof_with_unknown_validators() {
+    let testkit = create_testkit();
+
+    let (other_validator, _) = crypto::gen_keypair();
+    let proof: ListStateProof<Hash> = testkit
+        .harness
+        .get("/api/services/proof/history?index=0")
+        .await;
+    assert!(proof.verify(&[other_validator], SERVICE_ID, 1).is_err());
+
+    let path = format!(
+        "/api/services/proof/balance?key={}",
+        testkit.wallet_key.to_hex()
+    );
+    let proof: MapStateProof<PublicKey, u64> = testkit.harness.get(&path).await;
+    assert!(proof.verify(&[other_validator], SERVICE_ID, 0).is_err());
+}
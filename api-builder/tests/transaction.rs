@@ -0,0 +1,118 @@
+#[macro_use]
+extern crate exonum;
+
+use actix_web::http::StatusCode;
+use actix_web::test::{self, TestRequest};
+
+use api_builder::error::Error;
+use api_builder::service::{Service, ServiceApiContextMut, ServiceApiInitializer,
+                           TransactionResponse};
+use api_builder::testing::TestHarness;
+
+use exonum::blockchain::{ExecutionResult, Transaction};
+use exonum::crypto::{self, CryptoHash, Hash, PublicKey, Signature};
+use exonum::messages::Message;
+use exonum::node::ExternalMessage;
+use exonum::storage::Fork;
+
+transactions! {
+    TestTransactions {
+        const SERVICE_ID = 1;
+
+        struct Increment {
+            author: &PublicKey,
+            seed: u64,
+        }
+    }
+}
+
+impl Transaction for Increment {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.author())
+    }
+
+    fn execute(&self, _: &mut Fork) -> ExecutionResult {
+        Ok(())
+    }
+}
+
+struct TestService;
+
+impl Service for TestService {
+    fn service_name(&self) -> &str {
+        "test"
+    }
+
+    fn initialize_api(&self, initializer: &mut ServiceApiInitializer) {
+        initializer
+            .public_api()
+            .transaction::<TestTransactions>("transaction")
+            .endpoint(
+                "increment",
+                |context: &ServiceApiContextMut, transaction: Increment| -> Result<Hash, Error> {
+                    context.send_transaction(Box::new(transaction))
+                },
+            );
+    }
+}
+
+#[actix_web::test]
+async fn test_submit_transaction() {
+    let mut harness = TestHarness::with_service(TestService).unwrap();
+    let service = harness.public_service().await;
+
+    let (public_key, secret_key) = crypto::gen_keypair();
+    let transaction = Increment::new(&public_key, 42, &secret_key);
+
+    let request = TestRequest::post()
+        .uri("/api/services/test/transaction")
+        .set_json(&transaction)
+        .to_request();
+    let response: TransactionResponse = test::call_and_read_body_json(&service, request).await;
+    assert_eq!(response.tx_hash, transaction.hash());
+
+    match harness.api_message() {
+        Some(ExternalMessage::Transaction(tx)) => assert_eq!(tx.hash(), transaction.hash()),
+        _ => panic!("Transaction should be sent to the node"),
+    }
+}
+
+#[actix_web::test]
+async fn test_submit_incorrect_transaction() {
+    let mut harness = TestHarness::with_service(TestService).unwrap();
+    let service = harness.public_service().await;
+
+    let (public_key, _) = crypto::gen_keypair();
+    let transaction = Increment::new_with_signature(&public_key, 42, &Signature::zero());
+
+    let request = TestRequest::post()
+        .uri("/api/services/test/transaction")
+        .set_json(&transaction)
+        .to_request();
+    let response = test::call_service(&service, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(harness.api_message().is_none());
+}
+
+/// The custom mutable endpoints submit the transactions with the context, the read-only
+/// ones cannot do it, see `ServiceApiContextMut::send_transaction`.
+#[actix_web::test]
+async fn test_submit_transaction_from_mutable_endpoint() {
+    let mut harness = TestHarness::with_service(TestService).unwrap();
+    let service = harness.public_service().await;
+
+    let (public_key, secret_key) = crypto::gen_keypair();
+    let transaction = Increment::new(&public_key, 7, &secret_key);
+
+    let request = TestRequest::post()
+        .uri("/api/services/test/increment")
+        .set_json(&transaction)
+        .to_request();
+    let tx_hash: Hash = test::call_and_read_body_json(&service, request).await;
+    assert_eq!(tx_hash, transaction.hash());
+
+    match harness.api_message() {
+        Some(ExternalMessage::Transaction(tx)) => assert_eq!(tx.hash(), transaction.hash()),
+        _ => panic!("Transaction should be sent to the node"),
+    }
+}
@@ -0,0 +1,137 @@
+#[macro_use]
+extern crate serde_json;
+
+use warp::http::StatusCode;
+
+use api_builder::error::Error;
+use api_builder::service::ServiceApiContext;
+use api_builder::testing::TestHarness;
+use api_builder::warp_backend::{self, BackendBuilder, MAX_BODY_SIZE};
+
+use crate::common::{TestService, WarpClient};
+
+mod common;
+
+#[tokio::test]
+async fn test_public_api() {
+    let harness: TestHarness<BackendBuilder> = TestHarness::with_service(TestService).unwrap();
+    let client = WarpClient(harness.public_routes());
+    common::check_public_api(&client).await;
+}
+
+#[tokio::test]
+async fn test_private_api() {
+    let harness: TestHarness<BackendBuilder> = TestHarness::with_service(TestService).unwrap();
+    let client = WarpClient(harness.private_routes());
+    common::check_private_api(&client).await;
+}
+
+#[tokio::test]
+async fn test_error_responses() {
+    let harness: TestHarness<BackendBuilder> = TestHarness::with_service(TestService).unwrap();
+    let client = WarpClient(harness.public_routes());
+    common::check_error_responses(&client).await;
+}
+
+#[tokio::test]
+async fn test_endpoints_listing() {
+    let harness: TestHarness<BackendBuilder> = TestHarness::with_service(TestService).unwrap();
+    let client = WarpClient(harness.public_routes());
+    common::check_endpoints_listing(&client).await;
+}
+
+#[tokio::test]
+async fn test_batch() {
+    let harness: TestHarness<BackendBuilder> = TestHarness::with_service(TestService).unwrap();
+    let client = WarpClient(harness.public_routes());
+    common::check_batch(&client).await;
+}
+
+#[tokio::test]
+async fn test_request_id() {
+    let harness: TestHarness<BackendBuilder> = TestHarness::with_service(TestService).unwrap();
+    let client = WarpClient(harness.private_routes());
+    common::check_request_id(&client).await;
+}
+
+#[tokio::test]
+async fn test_signed_requests() {
+    let harness: TestHarness<BackendBuilder> = TestHarness::with_service(TestService).unwrap();
+    let client = WarpClient(harness.private_routes());
+    common::check_signed_requests(&client).await;
+}
+
+#[tokio::test]
+async fn test_signed_batch() {
+    let harness: TestHarness<BackendBuilder> = TestHarness::with_service(TestService).unwrap();
+    let client = WarpClient(harness.private_routes());
+    common::check_signed_batch(&client).await;
+}
+
+#[tokio::test]
+async fn test_query_string() {
+    let harness: TestHarness<BackendBuilder> = TestHarness::with_service(TestService).unwrap();
+    let routes = harness.public_routes();
+
+    // The query is read from the query string, the request has no body.
+    let response = warp::test::request()
+        .path("/api/services/test/sum?a=2&b=3")
+        .reply(&routes)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(serde_json::from_slice::<u64>(response.body()).unwrap(), 5);
+
+    // The endpoint is mounted with the `GET` method only.
+    let response = warp::test::request()
+        .method("POST")
+        .path("/api/services/test/sum?a=2&b=3")
+        .reply(&routes)
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_body_limit() {
+    let harness: TestHarness<BackendBuilder> = TestHarness::with_service(TestService).unwrap();
+    let routes = harness.public_routes();
+
+    let body = vec![b' '; MAX_BODY_SIZE as usize + 1];
+    for path in &["/api/services/test/increment", "/batch"] {
+        let response = warp::test::request()
+            .method("POST")
+            .path(path)
+            .body(&body)
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    // The bodies of the chunked requests cannot be limited beforehand.
+    let response = warp::test::request()
+        .method("POST")
+        .path("/batch")
+        .header("transfer-encoding", "chunked")
+        .reply(&routes)
+        .await;
+    assert_eq!(response.status(), StatusCode::LENGTH_REQUIRED);
+}
+
+#[tokio::test]
+async fn test_standalone_backend() {
+    let mut backend = BackendBuilder::new();
+    backend.endpoint(
+        "ping",
+        |_: &ServiceApiContext, _: ()| -> Result<String, Error> { Ok("pong".to_owned()) },
+    );
+
+    let harness: TestHarness<BackendBuilder> = TestHarness::with_service(TestService).unwrap();
+    let apis = vec![("custom".to_owned(), backend.finish())];
+    let routes = warp_backend::routes(harness.context().clone(), apis);
+
+    let response = warp::test::request()
+        .path("/api/custom/ping")
+        .reply(&routes)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body(), "\"pong\"");
+}
@@ -1,8 +1,9 @@
 use http_api::warp_backend::Error;
-use http_api_derive::{http_api, http_api_endpoint, FromUrlQuery};
+use http_api_derive::{http_api, http_api_endpoint, ApiError, FromUrlQuery};
 use serde_derive::{Deserialize, Serialize};
 
 use std::{
+    fmt,
     net::SocketAddr,
     sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
 };
@@ -13,16 +14,30 @@ struct Query {
     second: u64,
 }
 
-#[http_api(warp = "serve_ping_interface")]
+#[derive(Debug, ApiError)]
+enum IncrementError {
+    #[api_error(status = 409, code = "overflow")]
+    Overflow,
+}
+
+impl fmt::Display for IncrementError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IncrementError::Overflow => f.write_str("Value is too big to be incremented"),
+        }
+    }
+}
+
+#[http_api(warp = "serve_ping_interface", jsonrpc = "serve_ping_jsonrpc")]
 trait PingInterface {
-    #[http_api_endpoint(method = "get")]
+    #[http_api_endpoint(method = "get", cache_ttl = "5s")]
     fn get(&self) -> Result<Query, Error>;
     #[http_api_endpoint(method = "get")]
     fn check(&self, query: Query) -> Result<bool, Error>;
     #[http_api_endpoint(method = "post")]
     fn set_value(&self, param: Query) -> Result<(), Error>;
-    #[http_api_endpoint(method = "post")]
-    fn increment(&self) -> Result<(), Error>;
+    #[http_api_endpoint(method = "post", rate_limit = "10/s")]
+    fn increment(&self) -> Result<(), IncrementError>;
 }
 
 #[derive(Debug, Default)]
@@ -39,11 +54,11 @@ impl ServiceImpl {
         Self::default()
     }
 
-    fn read(&self) -> RwLockReadGuard<ServiceInner> {
+    fn read(&self) -> RwLockReadGuard<'_, ServiceInner> {
         self.0.read().unwrap()
     }
 
-    fn write(&self) -> RwLockWriteGuard<ServiceInner> {
+    fn write(&self) -> RwLockWriteGuard<'_, ServiceInner> {
         self.0.write().unwrap()
     }
 }
@@ -69,14 +84,23 @@ impl PingInterface for ServiceImpl {
         Ok(())
     }
 
-    fn increment(&self) -> Result<(), Error> {
-        self.write().second += 1;
+    fn increment(&self) -> Result<(), IncrementError> {
+        let mut inner = self.write();
+        inner.second = inner
+            .second
+            .checked_add(1)
+            .ok_or(IncrementError::Overflow)?;
         Ok(())
     }
 }
 
 #[tokio::main]
 async fn main() {
+    let service = ServiceImpl::new();
     let addr: SocketAddr = "127.0.0.1:8080".parse().unwrap();
-    serve_ping_interface(ServiceImpl::new(), addr).await
+    let jsonrpc_addr: SocketAddr = "127.0.0.1:8081".parse().unwrap();
+    futures::join!(
+        serve_ping_interface(service.clone(), addr),
+        serve_ping_jsonrpc(service, jsonrpc_addr)
+    );
 }
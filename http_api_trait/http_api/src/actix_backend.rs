@@ -0,0 +1,205 @@
+use actix_web::{
+    http::{
+        header::{HeaderName, AUTHORIZATION, CONTENT_TYPE, ETAG, IF_NONE_MATCH, RETRY_AFTER},
+        StatusCode,
+    },
+    web, HttpRequest, HttpResponse,
+};
+use futures::future::{self, Ready};
+use serde::{de, ser};
+
+use std::{any::Any, fmt::Debug};
+
+use super::{
+    cache::{self, JsonBody, ResponseCache},
+    rate_limit::{retry_after_secs, ClientKey, Principal, RateLimiter},
+    EndpointOptions, ErrorBody, ErrorReply, FromUrlQuery,
+};
+
+fn header_value(request: &HttpRequest, name: HeaderName) -> Option<String> {
+    request
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(ToOwned::to_owned)
+}
+
+/// Responds with the given handler if the client has not exceeded the rate limit.
+fn rate_limited<F>(
+    limiter: Option<&RateLimiter>,
+    request: &HttpRequest,
+    handler: F,
+) -> Ready<HttpResponse>
+where
+    F: FnOnce() -> HttpResponse,
+{
+    let key = || {
+        let principal = request.extensions().get::<Principal>().cloned();
+        ClientKey::new(request.peer_addr(), principal)
+    };
+    let response = match limiter.map(|limiter| limiter.check(key())) {
+        Some(Err(retry_after)) => HttpResponse::TooManyRequests()
+            .header(RETRY_AFTER, retry_after_secs(retry_after).to_string())
+            .finish(),
+        _ => handler(),
+    };
+    future::ready(response)
+}
+
+/// Renders `ErrorReply` as the JSON error body. The other errors are logged by their `Debug`
+/// representation and rendered as the generic internal error with the 500 status.
+fn error_response<E: Debug + 'static>(e: E) -> HttpResponse {
+    match (&e as &dyn Any).downcast_ref::<ErrorReply>() {
+        Some(ErrorReply { status, body }) => {
+            let status = StatusCode::from_u16(*status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            HttpResponse::build(status).json(body)
+        }
+        None => {
+            log::error!("Endpoint failed: {:?}", e);
+            HttpResponse::InternalServerError().json(ErrorBody::internal())
+        }
+    }
+}
+
+fn json_response<R, E>(result: Result<R, E>) -> HttpResponse
+where
+    R: ser::Serialize,
+    E: Debug + 'static,
+{
+    match result {
+        Ok(value) => HttpResponse::Ok().json(value),
+        Err(e) => error_response(e),
+    }
+}
+
+fn cached_json_response<F, R, E>(
+    cache: Option<&ResponseCache>,
+    path_and_query: &str,
+    request: &HttpRequest,
+    handler: F,
+) -> HttpResponse
+where
+    F: FnOnce() -> Result<R, E>,
+    R: ser::Serialize,
+    E: Debug + 'static,
+{
+    let authorization = header_value(request, AUTHORIZATION);
+    let key = cache::cache_key(path_and_query, authorization.as_deref());
+    let body = cache::get_or_compute(cache, key, || {
+        let value = handler().map_err(error_response)?;
+        JsonBody::new(&value).map_err(|e| {
+            log::error!("Unable to serialize the response: {}", e);
+            HttpResponse::InternalServerError().json(ErrorBody::internal())
+        })
+    });
+    let body = match body {
+        Ok(body) => body,
+        Err(response) => return response,
+    };
+
+    let if_none_match = header_value(request, IF_NONE_MATCH);
+    if if_none_match.is_some_and(|tags| body.matches(&tags)) {
+        HttpResponse::NotModified()
+            .header(ETAG, body.etag.as_str())
+            .finish()
+    } else {
+        HttpResponse::Ok()
+            .header(ETAG, body.etag.as_str())
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.bytes.clone())
+    }
+}
+
+pub fn simple_get<F, R, E>(
+    config: &mut web::ServiceConfig,
+    name: &'static str,
+    options: EndpointOptions,
+    handler: F,
+) where
+    F: Fn() -> Result<R, E> + Clone + 'static,
+    R: ser::Serialize,
+    E: Debug + 'static,
+{
+    let route = web::get().to(move |request: HttpRequest| {
+        rate_limited(options.rate_limit.as_ref(), &request, || {
+            cached_json_response(options.cache.as_ref(), name, &request, &handler)
+        })
+    });
+    config.route(&format!("/{}", name), route);
+}
+
+pub fn query_get<F, Q, R, E>(
+    config: &mut web::ServiceConfig,
+    name: &'static str,
+    options: EndpointOptions,
+    handler: F,
+) where
+    F: Fn(Q) -> Result<R, E> + Clone + 'static,
+    Q: FromUrlQuery,
+    R: ser::Serialize,
+    E: Debug + 'static,
+{
+    let route = web::get().to(move |request: HttpRequest| {
+        rate_limited(options.rate_limit.as_ref(), &request, || {
+            let raw_query = request.query_string();
+            let query = match Q::from_query_str(raw_query) {
+                Ok(query) => query,
+                Err(e) => {
+                    return HttpResponse::BadRequest()
+                        .json(ErrorBody::new("incorrect_query", e.to_string()))
+                }
+            };
+
+            let path_and_query = format!("{}?{}", name, raw_query);
+            cached_json_response(options.cache.as_ref(), &path_and_query, &request, || {
+                handler(query)
+            })
+        })
+    });
+    config.route(&format!("/{}", name), route);
+}
+
+pub fn simple_post<F, R, E>(
+    config: &mut web::ServiceConfig,
+    name: &'static str,
+    options: EndpointOptions,
+    handler: F,
+) where
+    F: Fn() -> Result<R, E> + Clone + 'static,
+    R: ser::Serialize,
+    E: Debug + 'static,
+{
+    let route = web::post().to(move |request: HttpRequest| {
+        rate_limited(options.rate_limit.as_ref(), &request, || {
+            json_response(handler())
+        })
+    });
+    config.route(&format!("/{}", name), route);
+}
+
+pub fn params_post<F, Q, R, E>(
+    config: &mut web::ServiceConfig,
+    name: &'static str,
+    options: EndpointOptions,
+    handler: F,
+) where
+    F: Fn(Q) -> Result<R, E> + Clone + 'static,
+    Q: de::DeserializeOwned + 'static,
+    R: ser::Serialize,
+    E: Debug + 'static,
+{
+    // The body is parsed only after the rate limit check, so the throttled clients
+    // are answered with 429 whatever they send.
+    let route = web::post().to(move |request: HttpRequest, body: web::Bytes| {
+        rate_limited(
+            options.rate_limit.as_ref(),
+            &request,
+            || match serde_json::from_slice(&body) {
+                Ok(params) => json_response(handler(params)),
+                Err(e) => HttpResponse::BadRequest()
+                    .json(ErrorBody::new("incorrect_params", e.to_string())),
+            },
+        )
+    });
+    config.route(&format!("/{}", name), route);
+}
@@ -0,0 +1,169 @@
+use serde::ser;
+use sha2::{Digest, Sha256};
+
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Number of cached responses after which the expired entries are evicted.
+const EVICTION_THRESHOLD: usize = 1024;
+
+/// Maximum number of cached responses, the oldest ones are evicted beyond it.
+pub const MAX_CACHED_RESPONSES: usize = 4096;
+
+/// Serialized JSON response body along with its strong entity tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonBody {
+    pub bytes: Vec<u8>,
+    pub etag: String,
+}
+
+impl JsonBody {
+    pub fn new<T: ser::Serialize>(value: &T) -> serde_json::Result<Self> {
+        let bytes = serde_json::to_vec(value)?;
+        // The tag is derived with the fixed hash function, so it stays the same across
+        // the restarts and upgrades of the server.
+        let etag = format!("\"{:x}-{}\"", bytes.len(), sha256_hex(&bytes, 16));
+        Ok(Self { bytes, etag })
+    }
+
+    /// Checks whether the `If-None-Match` header value matches this body.
+    pub fn matches(&self, if_none_match: &str) -> bool {
+        if_none_match.split(',').map(str::trim).any(|tag| {
+            // `If-None-Match` uses the weak comparison function.
+            tag == "*" || tag.trim_start_matches("W/") == self.etag
+        })
+    }
+}
+
+fn sha256_hex(bytes: &[u8], len: usize) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .take(len)
+        .fold(String::with_capacity(len * 2), |mut hex, byte| {
+            write!(hex, "{:02x}", byte).unwrap();
+            hex
+        })
+}
+
+/// Builds the key of the cached response to the request with the given path and query.
+/// The response may depend on the caller, so the responses to the requests with the different
+/// `Authorization` header values are cached separately. Only the hash of the header value
+/// is kept in the key.
+pub fn cache_key(path_and_query: &str, authorization: Option<&str>) -> String {
+    match authorization {
+        Some(authorization) => format!(
+            "{}#{}",
+            path_and_query,
+            sha256_hex(authorization.as_bytes(), 32)
+        ),
+        None => path_and_query.to_owned(),
+    }
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    body: Arc<JsonBody>,
+    expires_at: Instant,
+}
+
+/// In-memory cache of the endpoint responses keyed by the request path, query and caller,
+/// see `cache_key`.
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    ttl: Duration,
+    entries: Arc<Mutex<HashMap<String, CacheEntry>>>,
+}
+
+impl ResponseCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Arc::default(),
+        }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Returns the cached body if it has not expired yet.
+    pub fn get(&self, key: &str) -> Option<Arc<JsonBody>> {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+
+        match entries.get(key) {
+            Some(entry) if entry.expires_at > now => Some(entry.body.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Returns the number of cached responses, including the expired ones.
+    pub fn cached_responses(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn insert(&self, key: String, body: Arc<JsonBody>) {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+
+        if entries.len() >= EVICTION_THRESHOLD && !entries.contains_key(&key) {
+            entries.retain(|_, entry| entry.expires_at > now);
+            evict_oldest(&mut entries);
+        }
+
+        entries.insert(
+            key,
+            CacheEntry {
+                body,
+                expires_at: now + self.ttl,
+            },
+        );
+    }
+}
+
+/// Drops the oldest responses, if there are still too many of them after dropping
+/// the expired ones. A quarter of the responses is dropped at once, so the eviction
+/// does not happen on each insertion.
+fn evict_oldest(entries: &mut HashMap<String, CacheEntry>) {
+    if entries.len() < MAX_CACHED_RESPONSES {
+        return;
+    }
+
+    // All the entries have the same TTL, so the earliest to expire are the oldest ones.
+    let mut expirations = entries
+        .values()
+        .map(|entry| entry.expires_at)
+        .collect::<Vec<_>>();
+    let evicted = entries.len() - MAX_CACHED_RESPONSES * 3 / 4;
+    expirations.select_nth_unstable(evicted - 1);
+    let threshold = expirations[evicted - 1];
+    entries.retain(|_, entry| entry.expires_at > threshold);
+}
+
+/// Returns the cached body for the given key or computes it, caching only the successful result.
+pub fn get_or_compute<F, E>(
+    cache: Option<&ResponseCache>,
+    key: String,
+    compute: F,
+) -> Result<Arc<JsonBody>, E>
+where
+    F: FnOnce() -> Result<JsonBody, E>,
+{
+    if let Some(body) = cache.and_then(|cache| cache.get(&key)) {
+        return Ok(body);
+    }
+
+    let body = Arc::new(compute()?);
+    if let Some(cache) = cache {
+        cache.insert(key, body.clone());
+    }
+    Ok(body)
+}
@@ -0,0 +1,205 @@
+use serde::{de, ser};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+
+use std::{any::Any, fmt::Debug, time::Duration};
+
+use super::{rate_limit::retry_after_secs, ErrorReply};
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+/// Code for the errors returned by the API methods themselves.
+pub const SERVER_ERROR: i64 = -32000;
+/// The method call exceeds the rate limit of the method.
+pub const TOO_MANY_REQUESTS: i64 = -32001;
+
+/// Maximum number of the requests in a single batch.
+pub const MAX_BATCH_LENGTH: usize = 100;
+
+/// Maximum size of the JSON-RPC message in bytes, the larger ones are rejected
+/// before they are read.
+pub const MAX_MESSAGE_SIZE: u64 = 256 * 1024;
+
+const VERSION: &str = "2.0";
+
+/// JSON-RPC error object.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Error {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl Error {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    pub fn parse_error() -> Self {
+        Self::new(PARSE_ERROR, "Parse error")
+    }
+
+    pub fn invalid_request() -> Self {
+        Self::new(INVALID_REQUEST, "Invalid Request")
+    }
+
+    pub fn method_not_found() -> Self {
+        Self::new(METHOD_NOT_FOUND, "Method not found")
+    }
+
+    pub fn invalid_params(e: impl ToString) -> Self {
+        Self::new(INVALID_PARAMS, "Invalid params").with_data(e.to_string())
+    }
+
+    pub fn internal_error(e: impl ToString) -> Self {
+        Self::new(INTERNAL_ERROR, "Internal error").with_data(e.to_string())
+    }
+
+    /// The retry delay in whole seconds is passed in the `data` field.
+    pub fn too_many_requests(retry_after: Duration) -> Self {
+        Self::new(TOO_MANY_REQUESTS, "Too many requests").with_data(retry_after_secs(retry_after))
+    }
+
+    pub fn with_data(mut self, data: impl Into<Value>) -> Self {
+        self.data = Some(data.into());
+        self
+    }
+}
+
+/// Request identifier, the absent identifier means that the request is a notification.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Id {
+    Num(i64),
+    Str(String),
+    Null,
+}
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    method: String,
+    #[serde(default)]
+    params: Option<Value>,
+    // `Option<Id>` cannot tell `"id": null` from the missing field.
+    #[serde(default, deserialize_with = "deserialize_id")]
+    id: Option<Id>,
+}
+
+fn deserialize_id<'de, D: de::Deserializer<'de>>(deserializer: D) -> Result<Option<Id>, D::Error> {
+    de::Deserialize::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Serialize)]
+struct Response {
+    jsonrpc: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<Error>,
+    id: Id,
+}
+
+impl Response {
+    fn new(id: Id, result: Result<Value, Error>) -> Self {
+        let (result, error) = match result {
+            Ok(value) => (Some(value), None),
+            Err(e) => (None, Some(e)),
+        };
+
+        Self {
+            jsonrpc: VERSION,
+            result,
+            error,
+            id,
+        }
+    }
+}
+
+/// Deserializes the method parameters, the missing parameters are treated as `null`.
+pub fn parse_params<Q: de::DeserializeOwned>(params: Option<Value>) -> Result<Q, Error> {
+    serde_json::from_value(params.unwrap_or(Value::Null)).map_err(Error::invalid_params)
+}
+
+/// Converts the result of the API method into the JSON-RPC result.
+///
+/// The code of `ErrorReply` is passed in the `data` field of the JSON-RPC error. The other
+/// errors are logged and reported with the generic message, so their details are not disclosed.
+pub fn to_result<R, E>(result: Result<R, E>) -> Result<Value, Error>
+where
+    R: ser::Serialize,
+    E: Debug + 'static,
+{
+    match result {
+        Ok(value) => serde_json::to_value(value).map_err(Error::internal_error),
+        Err(e) => Err(match (&e as &dyn Any).downcast_ref::<ErrorReply>() {
+            Some(ErrorReply { body, .. }) => {
+                Error::new(SERVER_ERROR, body.message.clone()).with_data(body.code.clone())
+            }
+            None => {
+                log::error!("JSON-RPC method failed: {:?}", e);
+                Error::new(SERVER_ERROR, "Internal error")
+            }
+        }),
+    }
+}
+
+fn handle_request<F>(request: Value, dispatch: &F) -> Option<Response>
+where
+    F: Fn(&str, Option<Value>) -> Result<Value, Error>,
+{
+    // The identifier of the invalid request is echoed back if it is well-formed.
+    let id = request
+        .get("id")
+        .and_then(|id| serde_json::from_value::<Id>(id.clone()).ok())
+        .unwrap_or(Id::Null);
+    let request = match serde_json::from_value::<Request>(request) {
+        Ok(request) if request.jsonrpc == VERSION => request,
+        _ => return Some(Response::new(id, Err(Error::invalid_request()))),
+    };
+
+    let result = dispatch(&request.method, request.params);
+    request.id.map(|id| Response::new(id, result))
+}
+
+/// Handles a single or batch JSON-RPC message using the given method dispatcher.
+///
+/// Returns `None` if there is nothing to respond, i.e. the message contains only notifications.
+/// The batches of more than `MAX_BATCH_LENGTH` requests are rejected as a whole.
+pub fn handle_message<F>(message: &[u8], dispatch: F) -> Option<Value>
+where
+    F: Fn(&str, Option<Value>) -> Result<Value, Error>,
+{
+    let response = match serde_json::from_slice::<Value>(message) {
+        Ok(Value::Array(requests)) => {
+            if requests.is_empty() {
+                serde_json::to_value(Response::new(Id::Null, Err(Error::invalid_request())))
+            } else if requests.len() > MAX_BATCH_LENGTH {
+                let error = Error::invalid_request()
+                    .with_data(format!("Max batch length exceeded ({})", MAX_BATCH_LENGTH));
+                serde_json::to_value(Response::new(Id::Null, Err(error)))
+            } else {
+                let responses = requests
+                    .into_iter()
+                    .filter_map(|request| handle_request(request, &dispatch))
+                    .collect::<Vec<_>>();
+                if responses.is_empty() {
+                    return None;
+                }
+                serde_json::to_value(responses)
+            }
+        }
+        Ok(request) => serde_json::to_value(handle_request(request, &dispatch)?),
+        Err(_) => serde_json::to_value(Response::new(Id::Null, Err(Error::parse_error()))),
+    };
+
+    Some(response.expect("JSON-RPC response should be serializable"))
+}
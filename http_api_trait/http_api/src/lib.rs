@@ -1,14 +1,130 @@
 pub use serde_urlencoded::de::Error as ParseQueryError;
 
+use serde_derive::{Deserialize, Serialize};
+use warp::reject::Reject;
+
+use std::fmt::Display;
+
+pub mod actix_backend;
+pub mod cache;
+pub mod jsonrpc;
+pub mod rate_limit;
 pub mod warp_backend;
 
 #[doc(hidden)]
 pub mod export {
     pub use serde;
     pub use serde_derive;
+    pub use serde_json;
     pub use serde_urlencoded;
+    pub use warp;
 }
 
 pub trait FromUrlQuery: Sized {
     fn from_query_str(query: &str) -> Result<Self, ParseQueryError>;
 }
+
+/// Additional endpoint settings specified by the `http_api_endpoint` attribute.
+#[derive(Debug, Clone, Default)]
+pub struct EndpointOptions {
+    pub rate_limit: Option<rate_limit::RateLimiter>,
+    /// Response cache, only GET endpoints make use of it.
+    pub cache: Option<cache::ResponseCache>,
+}
+
+/// Error returned by the API methods, it is rendered as JSON `ErrorBody` with the given status.
+///
+/// Usually it is implemented via `#[derive(ApiError)]`.
+pub trait ApiError: Reject + Display {
+    /// HTTP status code of the error response.
+    fn status(&self) -> u16;
+    /// Machine-readable error code.
+    fn code(&self) -> &'static str;
+}
+
+/// JSON body of the error response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorBody {
+    pub code: String,
+    pub message: String,
+}
+
+impl ErrorBody {
+    pub fn new(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            code: code.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn from_api_error<E: ApiError>(e: &E) -> Self {
+        Self::new(e.code(), e.to_string())
+    }
+
+    /// Generic body of the unexpected errors, which does not disclose their details.
+    pub fn internal() -> Self {
+        Self::new("internal_error", "Internal server error")
+    }
+}
+
+/// Rendered `ApiError`, which the backends turn into the error response with the given status.
+///
+/// The endpoints generated by `http_api` convert the errors implementing `ApiError` into it,
+/// the other errors are passed to the backends as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorReply {
+    pub status: u16,
+    pub body: ErrorBody,
+}
+
+impl Reject for ErrorReply {}
+
+impl ErrorReply {
+    pub fn new<E: ApiError>(e: &E) -> Self {
+        Self {
+            status: e.status(),
+            body: ErrorBody::from_api_error(e),
+        }
+    }
+}
+
+/// Picks the conversion of the endpoint error depending on whether it implements `ApiError`.
+///
+/// `(&e).api_error_kind().convert(e)` resolves to `ApiKind` for the `ApiError` types, since
+/// its method takes the error by reference, and falls back to `OtherKind` via autoref.
+#[doc(hidden)]
+pub mod kind {
+    use super::{ApiError, ErrorReply};
+
+    pub struct Api;
+
+    pub trait ApiKind {
+        fn api_error_kind(&self) -> Api {
+            Api
+        }
+    }
+
+    impl<E: ApiError> ApiKind for E {}
+
+    impl Api {
+        pub fn convert<E: ApiError>(self, e: E) -> ErrorReply {
+            ErrorReply::new(&e)
+        }
+    }
+
+    pub struct Other;
+
+    pub trait OtherKind {
+        fn api_error_kind(&self) -> Other {
+            Other
+        }
+    }
+
+    impl<E> OtherKind for &E {}
+
+    impl Other {
+        pub fn convert<E>(self, e: E) -> E {
+            e
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Maximum number of tracked clients before the limiter drops idle buckets.
+pub const MAX_TRACKED_CLIENTS: usize = 4096;
+
+/// Amount of requests allowed during the given period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub period: Duration,
+}
+
+impl RateLimit {
+    pub fn new(requests: u32, period: Duration) -> Self {
+        assert!(requests > 0, "Rate limit should allow at least one request");
+        assert!(
+            period > Duration::from_secs(0),
+            "Rate limit period should not be zero"
+        );
+
+        Self { requests, period }
+    }
+
+    fn tokens_per_sec(&self) -> f64 {
+        f64::from(self.requests) / self.period.as_secs_f64()
+    }
+}
+
+/// Converts the retry delay into the `Retry-After` header value, which is measured in whole seconds.
+pub fn retry_after_secs(retry_after: Duration) -> u64 {
+    retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0)
+}
+
+/// Authenticated identity of the client, by which its requests are counted together
+/// regardless of its address.
+///
+/// The crate does not authenticate the clients itself: the application's authentication
+/// layer should insert the verified principal into the request extensions before
+/// the endpoint is reached, e.g. with `HttpMessage::extensions_mut` in an actix middleware
+/// or with `Request::extensions_mut` in a hyper service wrapping the warp filter.
+/// The requests without the principal are limited by the IP address of the client.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Principal(pub String);
+
+/// Identity of the client for which the requests are counted.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ClientKey {
+    /// Client authenticated as the given principal.
+    Principal(String),
+    /// Anonymous client identified by its IP address.
+    Addr(IpAddr),
+    /// Client without any known identity.
+    Unknown,
+}
+
+impl ClientKey {
+    /// Identifies the client by the authenticated principal or by the remote address.
+    /// Unverified credentials like the `Authorization` header value are not taken into
+    /// account, since the client could get a fresh bucket with each new value.
+    pub fn new(addr: Option<SocketAddr>, principal: Option<Principal>) -> Self {
+        match (principal, addr) {
+            (Some(Principal(principal)), _) => ClientKey::Principal(principal),
+            (None, Some(addr)) => ClientKey::Addr(addr.ip()),
+            (None, None) => ClientKey::Unknown,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    fn full(limit: RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(limit.requests),
+            updated_at: now,
+        }
+    }
+
+    fn refill(&mut self, limit: RateLimit, now: Instant) {
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed * limit.tokens_per_sec()).min(f64::from(limit.requests));
+        self.updated_at = now;
+    }
+
+    /// Checks whether the bucket would be refilled by now, without updating it.
+    fn is_full_at(&self, limit: RateLimit, now: Instant) -> bool {
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        self.tokens + elapsed * limit.tokens_per_sec() >= f64::from(limit.requests)
+    }
+}
+
+/// In-process token bucket rate limiter shared between all requests to the endpoint.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    buckets: Arc<Mutex<HashMap<ClientKey, TokenBucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            buckets: Arc::default(),
+        }
+    }
+
+    pub fn limit(&self) -> RateLimit {
+        self.limit
+    }
+
+    /// Returns the number of clients, which buckets are tracked by the limiter.
+    pub fn tracked_clients(&self) -> usize {
+        self.buckets.lock().unwrap().len()
+    }
+
+    /// Takes one token from the client's bucket. If the bucket is empty, returns
+    /// the duration after which the client may retry the request.
+    pub fn check(&self, key: ClientKey) -> Result<(), Duration> {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: ClientKey, now: Instant) -> Result<(), Duration> {
+        let limit = self.limit;
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() >= MAX_TRACKED_CLIENTS && !buckets.contains_key(&key) {
+            buckets.retain(|_, bucket| !bucket.is_full_at(limit, now));
+            evict_stalest(&mut buckets);
+        }
+
+        let bucket = buckets
+            .entry(key)
+            .or_insert_with(|| TokenBucket::full(limit, now));
+        bucket.refill(limit, now);
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            let missing = 1.0 - bucket.tokens;
+            Err(Duration::from_secs_f64(missing / limit.tokens_per_sec()))
+        }
+    }
+}
+
+/// Drops the buckets, which were used least recently, if the clients are still too many
+/// after dropping the idle ones. A quarter of the buckets is dropped at once, so the eviction
+/// does not happen on each request from a new client.
+fn evict_stalest(buckets: &mut HashMap<ClientKey, TokenBucket>) {
+    if buckets.len() < MAX_TRACKED_CLIENTS {
+        return;
+    }
+
+    let mut updates = buckets
+        .values()
+        .map(|bucket| bucket.updated_at)
+        .collect::<Vec<_>>();
+    let evicted = buckets.len() - MAX_TRACKED_CLIENTS * 3 / 4;
+    updates.select_nth_unstable(evicted - 1);
+    let threshold = updates[evicted - 1];
+    buckets.retain(|_, bucket| bucket.updated_at > threshold);
+}
@@ -1,66 +1,238 @@
 use serde::{de, ser};
-use warp::{filters::BoxedFilter, reject::Reject, Filter};
+use warp::{
+    filters::BoxedFilter,
+    http::{
+        header::{HeaderValue, CONTENT_TYPE, ETAG},
+        StatusCode,
+    },
+    hyper::{body::Bytes, Body},
+    reject::{Reject, Rejection},
+    reply::Response,
+    Filter, Reply,
+};
 
-use super::FromUrlQuery;
+use std::{collections::HashMap, fmt, time::Duration};
+
+use super::{
+    cache::{self, JsonBody, ResponseCache},
+    jsonrpc,
+    rate_limit::{retry_after_secs, ClientKey, Principal, RateLimiter},
+    ApiError, EndpointOptions, ErrorReply, FromUrlQuery,
+};
 
 #[derive(Debug)]
 pub struct Error;
 
 impl Reject for Error {}
 
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Internal server error")
+    }
+}
+
+impl ApiError for Error {
+    fn status(&self) -> u16 {
+        500
+    }
+
+    fn code(&self) -> &'static str {
+        "internal_error"
+    }
+}
+
 #[derive(Debug)]
 pub struct IncorrectQuery;
 
 impl Reject for IncorrectQuery {}
 
+impl fmt::Display for IncorrectQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Incorrect query")
+    }
+}
+
+impl ApiError for IncorrectQuery {
+    fn status(&self) -> u16 {
+        400
+    }
+
+    fn code(&self) -> &'static str {
+        "incorrect_query"
+    }
+}
+
+fn reject<E: ApiError>(e: E) -> Rejection {
+    warp::reject::custom(ErrorReply::new(&e))
+}
+
+#[derive(Debug)]
+pub struct TooManyRequests {
+    pub retry_after: Duration,
+}
+
+impl Reject for TooManyRequests {}
+
 pub type JsonReply = BoxedFilter<(warp::reply::Json,)>;
 
-pub fn simple_get<F, R, E>(name: &'static str, handler: F) -> JsonReply
+pub type CachedJsonReply = BoxedFilter<(Response,)>;
+
+fn client_key() -> BoxedFilter<(ClientKey,)> {
+    let principal = warp::ext::get::<Principal>()
+        .map(Some)
+        .or(warp::any().map(|| None))
+        .unify();
+    warp::addr::remote()
+        .and(principal)
+        .map(ClientKey::new)
+        .boxed()
+}
+
+fn rate_limit(limiter: Option<RateLimiter>) -> BoxedFilter<()> {
+    client_key()
+        .and_then(move |client| {
+            let limiter = limiter.clone();
+            async move {
+                if let Some(limiter) = limiter {
+                    limiter.check(client).map_err(|retry_after| {
+                        warp::reject::custom(TooManyRequests { retry_after })
+                    })?;
+                }
+                Ok::<_, Rejection>(())
+            }
+        })
+        .untuple_one()
+        .boxed()
+}
+
+/// Converts rejections produced by the endpoint filters into the corresponding HTTP responses.
+pub async fn handle_rejection(err: Rejection) -> Result<Response, Rejection> {
+    if let Some(TooManyRequests { retry_after }) = err.find() {
+        let secs = retry_after_secs(*retry_after);
+        let reply = warp::reply::with_status(warp::reply(), StatusCode::TOO_MANY_REQUESTS);
+        return Ok(
+            warp::reply::with_header(reply, "retry-after", secs.to_string()).into_response(),
+        );
+    }
+
+    if let Some(ErrorReply { status, body }) = err.find() {
+        let status = StatusCode::from_u16(*status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        return Ok(warp::reply::with_status(warp::reply::json(body), status).into_response());
+    }
+
+    Err(err)
+}
+
+fn json_response(body: &JsonBody, if_none_match: Option<&str>) -> Response {
+    let mut response = if if_none_match.is_some_and(|tags| body.matches(tags)) {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = StatusCode::NOT_MODIFIED;
+        response
+    } else {
+        let mut response = Response::new(Body::from(body.bytes.clone()));
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        response
+    };
+
+    let etag = HeaderValue::from_str(&body.etag).expect("ETag should be a valid header value");
+    response.headers_mut().insert(ETAG, etag);
+    response
+}
+
+fn cached_json_reply<F, R, E>(
+    cache: Option<&ResponseCache>,
+    path_and_query: &str,
+    authorization: Option<String>,
+    if_none_match: Option<String>,
+    handler: F,
+) -> Result<Response, Rejection>
+where
+    F: FnOnce() -> Result<R, E>,
+    R: ser::Serialize,
+    E: Reject,
+{
+    let key = cache::cache_key(path_and_query, authorization.as_deref());
+    let body = cache::get_or_compute(cache, key, || {
+        let value = handler().map_err(warp::reject::custom)?;
+        JsonBody::new(&value).map_err(|_| reject(Error))
+    })?;
+
+    Ok(json_response(&body, if_none_match.as_deref()))
+}
+
+pub fn simple_get<F, R, E>(
+    name: &'static str,
+    options: EndpointOptions,
+    handler: F,
+) -> CachedJsonReply
 where
     F: Fn() -> Result<R, E> + Clone + Send + Sync + 'static,
     R: ser::Serialize,
     E: Reject,
 {
+    let cache = options.cache;
     warp::get()
         .and(warp::path(name))
-        .and_then(move || {
-            let handler = handler.clone();
-            async move {
-                match handler() {
-                    Ok(value) => Ok(warp::reply::json(&value)),
-                    Err(e) => Err(warp::reject::custom(e)),
+        .and(rate_limit(options.rate_limit))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::header::optional::<String>("if-none-match"))
+        .and_then(
+            move |authorization: Option<String>, if_none_match: Option<String>| {
+                let handler = handler.clone();
+                let cache = cache.clone();
+                async move {
+                    cached_json_reply(cache.as_ref(), name, authorization, if_none_match, handler)
                 }
-            }
-        })
+            },
+        )
         .boxed()
 }
 
-pub fn query_get<F, Q, R, E>(name: &'static str, handler: F) -> JsonReply
+pub fn query_get<F, Q, R, E>(
+    name: &'static str,
+    options: EndpointOptions,
+    handler: F,
+) -> CachedJsonReply
 where
     F: Fn(Q) -> Result<R, E> + Clone + Send + Sync + 'static,
     Q: FromUrlQuery,
     R: ser::Serialize,
     E: Reject,
 {
+    let cache = options.cache;
     warp::get()
         .and(warp::path(name))
+        .and(rate_limit(options.rate_limit))
         .and(warp::filters::query::raw())
-        .and_then(move |raw_query: String| {
-            let handler = handler.clone();
-            async move {
-                let query = Q::from_query_str(&raw_query)
-                    .map_err(|_| warp::reject::custom(IncorrectQuery))?;
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::header::optional::<String>("if-none-match"))
+        .and_then(
+            move |raw_query: String,
+                  authorization: Option<String>,
+                  if_none_match: Option<String>| {
+                let handler = handler.clone();
+                let cache = cache.clone();
+                async move {
+                    let query =
+                        Q::from_query_str(&raw_query).map_err(|_| reject(IncorrectQuery))?;
 
-                match handler(query) {
-                    Ok(value) => Ok(warp::reply::json(&value)),
-                    Err(e) => Err(warp::reject::custom(e)),
+                    let path_and_query = format!("{}?{}", name, raw_query);
+                    cached_json_reply(
+                        cache.as_ref(),
+                        &path_and_query,
+                        authorization,
+                        if_none_match,
+                        || handler(query),
+                    )
                 }
-            }
-        })
+            },
+        )
         .boxed()
 }
 
-pub fn simple_post<F, R, E>(name: &'static str, handler: F) -> JsonReply
+pub fn simple_post<F, R, E>(name: &'static str, options: EndpointOptions, handler: F) -> JsonReply
 where
     F: Fn() -> Result<R, E> + Clone + Send + Sync + 'static,
     R: ser::Serialize,
@@ -68,6 +240,7 @@ where
 {
     warp::post()
         .and(warp::path(name))
+        .and(rate_limit(options.rate_limit))
         .and_then(move || {
             let handler = handler.clone();
             async move {
@@ -80,15 +253,20 @@ where
         .boxed()
 }
 
-pub fn params_post<F, Q, R, E>(name: &'static str, handler: F) -> JsonReply
+pub fn params_post<F, Q, R, E>(
+    name: &'static str,
+    options: EndpointOptions,
+    handler: F,
+) -> JsonReply
 where
     F: Fn(Q) -> Result<R, E> + Clone + Send + Sync + 'static,
     Q: de::DeserializeOwned + Send + 'static,
     R: ser::Serialize,
     E: Reject,
 {
-    warp::get()
+    warp::post()
         .and(warp::path(name))
+        .and(rate_limit(options.rate_limit))
         .and(warp::body::json())
         .and_then(move |query| {
             let handler = handler.clone();
@@ -101,3 +279,56 @@ where
         })
         .boxed()
 }
+
+/// Single JSON-RPC 2.0 endpoint which passes all the calls to the given method dispatcher.
+pub fn jsonrpc<F>(dispatch: F) -> BoxedFilter<(Response,)>
+where
+    F: Fn(&str, Option<serde_json::Value>) -> Result<serde_json::Value, jsonrpc::Error>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+{
+    jsonrpc_with_options(dispatch, HashMap::new())
+}
+
+/// Same as `jsonrpc`, but applies the options of the methods with the given names.
+///
+/// Only the rate limit is taken into account, every call of a batch is counted separately.
+/// The messages larger than `jsonrpc::MAX_MESSAGE_SIZE` are rejected with 413.
+pub fn jsonrpc_with_options<F>(
+    dispatch: F,
+    options: HashMap<&'static str, EndpointOptions>,
+) -> BoxedFilter<(Response,)>
+where
+    F: Fn(&str, Option<serde_json::Value>) -> Result<serde_json::Value, jsonrpc::Error>
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+{
+    warp::post()
+        .and(warp::path::end())
+        .and(client_key())
+        .and(warp::body::content_length_limit(jsonrpc::MAX_MESSAGE_SIZE))
+        .and(warp::body::bytes())
+        .map(move |client: ClientKey, body: Bytes| {
+            let dispatch = |method: &str, params| {
+                if let Some(limiter) = options
+                    .get(method)
+                    .and_then(|options| options.rate_limit.as_ref())
+                {
+                    limiter
+                        .check(client.clone())
+                        .map_err(jsonrpc::Error::too_many_requests)?;
+                }
+                dispatch(method, params)
+            };
+
+            match jsonrpc::handle_message(&body, dispatch) {
+                Some(response) => warp::reply::json(&response).into_response(),
+                None => StatusCode::NO_CONTENT.into_response(),
+            }
+        })
+        .boxed()
+}
@@ -0,0 +1,196 @@
+use actix_web::{dev::Service, http::StatusCode, test, App, HttpMessage};
+use http_api::rate_limit::Principal;
+use http_api::warp_backend::Error;
+use http_api_derive::{http_api, http_api_endpoint, FromUrlQuery};
+use serde_derive::{Deserialize, Serialize};
+
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, FromUrlQuery, Deserialize, Serialize)]
+struct Value {
+    value: u64,
+}
+
+#[http_api(actix = "configure_counter")]
+trait CounterInterface {
+    #[http_api_endpoint(method = "get")]
+    fn get(&self) -> Result<Value, Error>;
+    #[http_api_endpoint(method = "get", rename = "is-equal")]
+    fn is_equal(&self, query: Value) -> Result<bool, Error>;
+    #[http_api_endpoint(method = "post")]
+    fn set(&self, param: Value) -> Result<(), Error>;
+    #[http_api_endpoint(method = "post", rate_limit = "1/m")]
+    fn increment(&self) -> Result<u64, Error>;
+    #[http_api_endpoint(method = "post", rate_limit = "1/m")]
+    fn add(&self, param: Value) -> Result<u64, Error>;
+}
+
+#[derive(Clone, Default)]
+struct Counter(Arc<Mutex<u64>>);
+
+impl CounterInterface for Counter {
+    fn get(&self) -> Result<Value, Error> {
+        Ok(Value {
+            value: *self.0.lock().unwrap(),
+        })
+    }
+
+    fn is_equal(&self, query: Value) -> Result<bool, Error> {
+        Ok(*self.0.lock().unwrap() == query.value)
+    }
+
+    fn set(&self, param: Value) -> Result<(), Error> {
+        *self.0.lock().unwrap() = param.value;
+        Ok(())
+    }
+
+    fn increment(&self) -> Result<u64, Error> {
+        let mut value = self.0.lock().unwrap();
+        *value += 1;
+        Ok(*value)
+    }
+
+    fn add(&self, param: Value) -> Result<u64, Error> {
+        let mut value = self.0.lock().unwrap();
+        *value += param.value;
+        Ok(*value)
+    }
+}
+
+#[actix_rt::test]
+async fn test_actix_endpoints() {
+    let counter = Counter::default();
+    let mut app =
+        test::init_service(App::new().configure(|config| configure_counter(counter, config))).await;
+
+    let request = test::TestRequest::post()
+        .uri("/set")
+        .set_json(&Value { value: 5 })
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get().uri("/get").to_request();
+    let value: Value = test::read_response_json(&mut app, request).await;
+    assert_eq!(value.value, 5);
+
+    let request = test::TestRequest::get()
+        .uri("/is-equal?value=5")
+        .to_request();
+    let is_equal: bool = test::read_response_json(&mut app, request).await;
+    assert!(is_equal);
+
+    let request = test::TestRequest::get()
+        .uri("/is-equal?other=5")
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let request = test::TestRequest::post().uri("/increment").to_request();
+    let value: u64 = test::read_response_json(&mut app, request).await;
+    assert_eq!(value, 6);
+
+    let request = test::TestRequest::post().uri("/increment").to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key("retry-after"));
+}
+
+#[actix_rt::test]
+async fn test_actix_params_after_rate_limit() {
+    let counter = Counter::default();
+    let mut app =
+        test::init_service(App::new().configure(|config| configure_counter(counter, config))).await;
+
+    let request = test::TestRequest::post()
+        .uri("/set")
+        .set_payload("{ not a json")
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let request = test::TestRequest::post()
+        .uri("/add")
+        .set_json(&Value { value: 2 })
+        .to_request();
+    let value: u64 = test::read_response_json(&mut app, request).await;
+    assert_eq!(value, 2);
+
+    // The throttled client is rejected before its body is parsed.
+    let request = test::TestRequest::post()
+        .uri("/add")
+        .set_payload("{ not a json")
+        .to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[actix_rt::test]
+async fn test_actix_server() {
+    let counter = Counter::default();
+    let server = test::start(move || {
+        let counter = counter.clone();
+        App::new().configure(move |config| configure_counter(counter, config))
+    });
+
+    let response = server
+        .post("/set")
+        .send_json(&Value { value: 3 })
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let value: Value = server
+        .get("/get")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(value.value, 3);
+
+    let mut response = server.get("/is-equal?value=3").send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let is_equal: bool = response.json().await.unwrap();
+    assert!(is_equal);
+
+    let response = server.get("/is-equal?other=3").send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_rt::test]
+async fn test_actix_rate_limit_by_principal() {
+    let counter = Counter::default();
+    // Simplified authentication layer, which trusts the `x-user` header.
+    let app = App::new()
+        .wrap_fn(|request, service| {
+            let principal = request
+                .headers()
+                .get("x-user")
+                .and_then(|value| value.to_str().ok())
+                .map(|user| Principal(user.to_owned()));
+            if let Some(principal) = principal {
+                request.extensions_mut().insert(principal);
+            }
+            service.call(request)
+        })
+        .configure(|config| configure_counter(counter, config));
+    let mut app = test::init_service(app).await;
+
+    // The clients are counted by the principal, not by the address.
+    let requests = vec![
+        ("alice", "127.0.0.1:8000", StatusCode::OK),
+        ("bob", "127.0.0.1:8000", StatusCode::OK),
+        ("alice", "127.0.0.2:8000", StatusCode::TOO_MANY_REQUESTS),
+    ];
+    for (user, addr, expected) in requests {
+        let request = test::TestRequest::post()
+            .uri("/increment")
+            .header("x-user", user)
+            .peer_addr(addr.parse().unwrap())
+            .to_request();
+        let response = test::call_service(&mut app, request).await;
+        assert_eq!(response.status(), expected);
+    }
+}
@@ -0,0 +1,139 @@
+use http_api::{
+    warp_backend::{handle_rejection, simple_get},
+    ApiError, EndpointOptions, ErrorBody, ErrorReply,
+};
+use http_api_derive::{http_api, http_api_endpoint, ApiError};
+use warp::{http::StatusCode, Filter};
+
+use std::fmt;
+
+#[derive(Debug, ApiError)]
+enum StorageError {
+    #[api_error(status = 404, code = "not_found")]
+    NotFound {
+        key: String,
+    },
+    #[api_error(status = 409)]
+    AlreadyExists(String),
+    Corrupted,
+    IOError,
+    HTTP2Unsupported,
+}
+
+// Empty enums are supported as well.
+#[derive(Debug, ApiError)]
+enum Never {}
+
+impl fmt::Display for Never {
+    fn fmt(&self, _f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {}
+    }
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StorageError::NotFound { key } => write!(f, "Key {} is not found", key),
+            StorageError::AlreadyExists(key) => write!(f, "Key {} already exists", key),
+            StorageError::Corrupted => f.write_str("Storage is corrupted"),
+            StorageError::IOError => f.write_str("I/O error"),
+            StorageError::HTTP2Unsupported => f.write_str("HTTP/2 is not supported"),
+        }
+    }
+}
+
+#[test]
+fn test_api_error_derive() {
+    let e = StorageError::NotFound {
+        key: "foo".to_owned(),
+    };
+    assert_eq!(e.status(), 404);
+    assert_eq!(e.code(), "not_found");
+
+    let e = StorageError::AlreadyExists("foo".to_owned());
+    assert_eq!(e.status(), 409);
+    assert_eq!(e.code(), "already_exists");
+
+    assert_eq!(StorageError::Corrupted.status(), 500);
+    assert_eq!(StorageError::Corrupted.code(), "corrupted");
+    assert_eq!(StorageError::IOError.code(), "io_error");
+    assert_eq!(StorageError::HTTP2Unsupported.code(), "http2_unsupported");
+}
+
+#[tokio::test]
+async fn test_api_error_response() {
+    let filter = simple_get("value", EndpointOptions::default(), || {
+        let e = StorageError::NotFound {
+            key: "value".to_owned(),
+        };
+        Err::<(), _>(ErrorReply::new(&e))
+    })
+    .recover(handle_rejection);
+
+    let response = warp::test::request().path("/value").reply(&filter).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let body: ErrorBody = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(body, ErrorBody::new("not_found", "Key value is not found"));
+}
+
+// Errors without `ApiError` implementation are accepted by the backends as well.
+#[derive(Debug)]
+struct LegacyError;
+
+impl warp::reject::Reject for LegacyError {}
+
+#[http_api(actix = "configure_storage")]
+trait StorageInterface {
+    #[http_api_endpoint(method = "get")]
+    fn get(&self) -> Result<u64, StorageError>;
+    #[http_api_endpoint(method = "get")]
+    fn get_legacy(&self) -> Result<u64, LegacyError>;
+    #[http_api_endpoint(method = "get")]
+    fn count(&self) -> Result<u64, Never>;
+}
+
+#[derive(Clone)]
+struct Storage;
+
+impl StorageInterface for Storage {
+    fn get(&self) -> Result<u64, StorageError> {
+        Err(StorageError::AlreadyExists("value".to_owned()))
+    }
+
+    fn get_legacy(&self) -> Result<u64, LegacyError> {
+        Err(LegacyError)
+    }
+
+    fn count(&self) -> Result<u64, Never> {
+        Ok(1)
+    }
+}
+
+#[actix_rt::test]
+async fn test_api_error_endpoint() {
+    use actix_web::{test, App};
+
+    let mut app =
+        test::init_service(App::new().configure(|config| configure_storage(Storage, config))).await;
+
+    let request = test::TestRequest::get().uri("/get").to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status().as_u16(), 409);
+    let body: ErrorBody = test::read_body_json(response).await;
+    assert_eq!(
+        body,
+        ErrorBody::new("already_exists", "Key value already exists")
+    );
+
+    let request = test::TestRequest::get().uri("/get_legacy").to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status().as_u16(), 500);
+    // The details of the other errors are not disclosed to the client.
+    let body: ErrorBody = test::read_body_json(response).await;
+    assert_eq!(body, ErrorBody::internal());
+
+    let request = test::TestRequest::get().uri("/count").to_request();
+    let response = test::call_service(&mut app, request).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
@@ -0,0 +1,122 @@
+use http_api::{
+    cache::{JsonBody, ResponseCache, MAX_CACHED_RESPONSES},
+    warp_backend::{query_get, Error},
+    EndpointOptions,
+};
+use http_api_derive::FromUrlQuery;
+use warp::http::StatusCode;
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+#[derive(FromUrlQuery)]
+struct CountQuery {
+    count: u64,
+}
+
+#[test]
+fn test_json_body_etag() {
+    let body = JsonBody::new(&vec![1, 2, 3]).unwrap();
+    assert_eq!(body.bytes, b"[1,2,3]");
+    // The tag does not depend on the build or the run of the server.
+    assert_eq!(body.etag, "\"7-a615eeaee21de5179de080de8c3052c8\"");
+    assert_eq!(body, JsonBody::new(&vec![1, 2, 3]).unwrap());
+    assert_ne!(body.etag, JsonBody::new(&vec![3, 2, 1]).unwrap().etag);
+
+    assert!(body.matches(&body.etag));
+    assert!(body.matches(&format!("\"foo\", W/{}", body.etag)));
+    assert!(body.matches("*"));
+    assert!(!body.matches("\"foo\""));
+}
+
+#[test]
+fn test_cache_evicts_oldest_responses() {
+    let cache = ResponseCache::new(Duration::from_secs(60));
+    let body = Arc::new(JsonBody::new(&()).unwrap());
+
+    // None of the responses expires, since the TTL is long enough.
+    for i in 0..MAX_CACHED_RESPONSES * 2 {
+        cache.insert(format!("/count?count={}", i), body.clone());
+        assert!(cache.cached_responses() <= MAX_CACHED_RESPONSES);
+    }
+
+    // The most recent response is kept.
+    let last = format!("/count?count={}", MAX_CACHED_RESPONSES * 2 - 1);
+    assert!(cache.get(&last).is_some());
+    assert!(cache.get("/count?count=0").is_none());
+}
+
+#[tokio::test]
+async fn test_cached_endpoint() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let options = EndpointOptions {
+        cache: Some(ResponseCache::new(Duration::from_secs(60))),
+        ..EndpointOptions::default()
+    };
+    let filter = query_get("double", options, {
+        let calls = calls.clone();
+        move |query: CountQuery| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, Error>(query.count * 2)
+        }
+    });
+
+    let response = warp::test::request()
+        .path("/double?count=2")
+        .reply(&filter)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body().as_ref(), b"4");
+    let etag = response.headers()["etag"].clone();
+
+    let response = warp::test::request()
+        .path("/double?count=2")
+        .header("if-none-match", etag.clone())
+        .reply(&filter)
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()["etag"], etag);
+    assert!(response.body().is_empty());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    // Requests with the other query are cached separately.
+    let response = warp::test::request()
+        .path("/double?count=3")
+        .reply(&filter)
+        .await;
+    assert_eq!(response.body().as_ref(), b"6");
+    assert_ne!(response.headers()["etag"], etag);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_cache_per_caller() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let options = EndpointOptions {
+        cache: Some(ResponseCache::new(Duration::from_secs(60))),
+        ..EndpointOptions::default()
+    };
+    let filter = query_get("double", options, {
+        let calls = calls.clone();
+        move |query: CountQuery| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok::<_, Error>(query.count * 2)
+        }
+    });
+
+    for caller in &["alice", "bob", "alice"] {
+        let response = warp::test::request()
+            .path("/double?count=2")
+            .header("authorization", *caller)
+            .reply(&filter)
+            .await;
+        assert_eq!(response.body().as_ref(), b"4");
+    }
+    // The response to the other caller is not served from the cache.
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
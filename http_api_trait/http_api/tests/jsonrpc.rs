@@ -0,0 +1,200 @@
+use http_api::{
+    jsonrpc::{self, handle_message, parse_params, to_result},
+    rate_limit::{RateLimit, RateLimiter},
+    warp_backend::Error,
+    EndpointOptions,
+};
+use serde_derive::Deserialize;
+use serde_json::{json, Value};
+use warp::http::StatusCode;
+
+use std::{collections::HashMap, time::Duration};
+
+#[derive(Deserialize)]
+struct SumParams {
+    a: u64,
+    b: u64,
+}
+
+fn dispatch(method: &str, params: Option<Value>) -> Result<Value, jsonrpc::Error> {
+    match method {
+        "sum" => {
+            let params: SumParams = parse_params(params)?;
+            to_result(Ok::<_, Error>(params.a + params.b))
+        }
+        "fail" => to_result(Err::<(), _>(Error)),
+        _ => Err(jsonrpc::Error::method_not_found()),
+    }
+}
+
+fn handle(message: Value) -> Option<Value> {
+    handle_message(message.to_string().as_bytes(), dispatch)
+}
+
+#[test]
+fn test_jsonrpc_single_request() {
+    let response = handle(json!({
+        "jsonrpc": "2.0",
+        "method": "sum",
+        "params": { "a": 1, "b": 2 },
+        "id": 1,
+    }));
+    assert_eq!(
+        response,
+        Some(json!({ "jsonrpc": "2.0", "result": 3, "id": 1 }))
+    );
+}
+
+#[test]
+fn test_jsonrpc_errors() {
+    let code = |response: Option<Value>| response.unwrap()["error"]["code"].clone();
+
+    let response = handle_message(b"{ not a json", dispatch);
+    assert_eq!(code(response.clone()), json!(jsonrpc::PARSE_ERROR));
+    assert_eq!(response.unwrap()["id"], Value::Null);
+
+    let response = handle(json!({ "jsonrpc": "1.0", "method": "sum", "id": 1 }));
+    assert_eq!(code(response.clone()), json!(jsonrpc::INVALID_REQUEST));
+    assert_eq!(response.unwrap()["id"], json!(1));
+
+    let response = handle(json!({ "jsonrpc": "2.0", "id": "b" }));
+    assert_eq!(code(response.clone()), json!(jsonrpc::INVALID_REQUEST));
+    assert_eq!(response.unwrap()["id"], json!("b"));
+
+    let response = handle(json!({ "jsonrpc": "2.0", "method": "sum", "id": [1] }));
+    assert_eq!(code(response.clone()), json!(jsonrpc::INVALID_REQUEST));
+    assert_eq!(response.unwrap()["id"], Value::Null);
+
+    let response = handle(json!({ "jsonrpc": "2.0", "method": "mul", "id": 1 }));
+    assert_eq!(code(response), json!(jsonrpc::METHOD_NOT_FOUND));
+
+    let response = handle(json!({ "jsonrpc": "2.0", "method": "sum", "params": [1], "id": 1 }));
+    assert_eq!(code(response), json!(jsonrpc::INVALID_PARAMS));
+
+    let response = handle(json!({ "jsonrpc": "2.0", "method": "fail", "id": "a" }));
+    assert_eq!(code(response.clone()), json!(jsonrpc::SERVER_ERROR));
+    let response = response.unwrap();
+    assert_eq!(response["id"], json!("a"));
+    // The details of the error are not disclosed to the client.
+    assert_eq!(response["error"]["message"], json!("Internal error"));
+
+    let response = handle(json!([]));
+    assert_eq!(code(response), json!(jsonrpc::INVALID_REQUEST));
+
+    let request =
+        json!({ "jsonrpc": "2.0", "method": "sum", "params": { "a": 1, "b": 2 }, "id": 1 });
+    let response = handle(Value::Array(vec![request; jsonrpc::MAX_BATCH_LENGTH + 1]));
+    assert_eq!(code(response), json!(jsonrpc::INVALID_REQUEST));
+}
+
+#[test]
+fn test_jsonrpc_notifications_and_batch() {
+    let notification = json!({ "jsonrpc": "2.0", "method": "sum", "params": { "a": 1, "b": 2 } });
+    assert_eq!(handle(notification.clone()), None);
+    assert_eq!(
+        handle(json!([notification.clone(), notification.clone()])),
+        None
+    );
+
+    let response = handle(json!([
+        { "jsonrpc": "2.0", "method": "sum", "params": { "a": 1, "b": 2 }, "id": 1 },
+        notification,
+        { "jsonrpc": "2.0", "method": "sum", "params": { "a": 2, "b": 2 }, "id": null },
+        1,
+    ]))
+    .unwrap();
+
+    let responses = response.as_array().unwrap();
+    assert_eq!(responses.len(), 3);
+    assert_eq!(
+        responses[0],
+        json!({ "jsonrpc": "2.0", "result": 3, "id": 1 })
+    );
+    assert_eq!(
+        responses[1],
+        json!({ "jsonrpc": "2.0", "result": 4, "id": null })
+    );
+    assert_eq!(
+        responses[2]["error"]["code"],
+        json!(jsonrpc::INVALID_REQUEST)
+    );
+}
+
+#[tokio::test]
+async fn test_jsonrpc_filter() {
+    let filter = http_api::warp_backend::jsonrpc(dispatch);
+
+    let response = warp::test::request()
+        .method("POST")
+        .path("/")
+        .json(&json!({ "jsonrpc": "2.0", "method": "sum", "params": { "a": 1, "b": 2 }, "id": 1 }))
+        .reply(&filter)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(body["result"], json!(3));
+
+    let response = warp::test::request()
+        .method("POST")
+        .path("/")
+        .json(&json!({ "jsonrpc": "2.0", "method": "sum", "params": { "a": 1, "b": 2 } }))
+        .reply(&filter)
+        .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn test_jsonrpc_filter_body_limit() {
+    let filter = http_api::warp_backend::jsonrpc(dispatch);
+
+    let response = warp::test::request()
+        .method("POST")
+        .path("/")
+        .body(vec![b' '; jsonrpc::MAX_MESSAGE_SIZE as usize + 1])
+        .reply(&filter)
+        .await;
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn test_jsonrpc_filter_rate_limit() {
+    let mut options = HashMap::new();
+    options.insert(
+        "sum",
+        EndpointOptions {
+            rate_limit: Some(RateLimiter::new(RateLimit::new(2, Duration::from_secs(60)))),
+            cache: None,
+        },
+    );
+    let filter = http_api::warp_backend::jsonrpc_with_options(dispatch, options);
+
+    let sum =
+        |id| json!({ "jsonrpc": "2.0", "method": "sum", "params": { "a": 1, "b": 2 }, "id": id });
+    let response = warp::test::request()
+        .method("POST")
+        .path("/")
+        .json(&json!([sum(1), sum(2), sum(3), { "jsonrpc": "2.0", "method": "fail", "id": 4 }]))
+        .reply(&filter)
+        .await;
+    let body: Value = serde_json::from_slice(response.body()).unwrap();
+    let responses = body.as_array().unwrap();
+    assert_eq!(responses[0]["result"], json!(3));
+    assert_eq!(responses[1]["result"], json!(3));
+    assert_eq!(
+        responses[2]["error"]["code"],
+        json!(jsonrpc::TOO_MANY_REQUESTS)
+    );
+    assert_eq!(responses[2]["error"]["data"], json!(30));
+    // The methods without the rate limit are not throttled.
+    assert_eq!(responses[3]["error"]["code"], json!(jsonrpc::SERVER_ERROR));
+
+    let response = warp::test::request()
+        .method("POST")
+        .path("/")
+        .json(&sum(5))
+        .reply(&filter)
+        .await;
+    let body: Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(body["error"]["code"], json!(jsonrpc::TOO_MANY_REQUESTS));
+    assert_eq!(body["id"], json!(5));
+}
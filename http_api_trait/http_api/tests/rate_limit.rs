@@ -0,0 +1,150 @@
+use http_api::{
+    rate_limit::{ClientKey, Principal, RateLimit, RateLimiter, MAX_TRACKED_CLIENTS},
+    warp_backend::{handle_rejection, params_post, simple_post, Error},
+    EndpointOptions,
+};
+use warp::{http::StatusCode, Filter};
+
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    time::Duration,
+};
+
+#[test]
+fn test_rate_limiter_exhausts_bucket() {
+    let limiter = RateLimiter::new(RateLimit::new(2, Duration::from_secs(60)));
+    let key = ClientKey::Principal("alice".to_owned());
+
+    assert!(limiter.check(key.clone()).is_ok());
+    assert!(limiter.check(key.clone()).is_ok());
+
+    let retry_after = limiter.check(key).unwrap_err();
+    assert!(retry_after > Duration::from_secs(0));
+    assert!(retry_after <= Duration::from_secs(30));
+    // Other clients have their own buckets.
+    assert!(limiter
+        .check(ClientKey::Principal("bob".to_owned()))
+        .is_ok());
+}
+
+#[test]
+fn test_rate_limiter_evicts_stalest_clients() {
+    let limiter = RateLimiter::new(RateLimit::new(2, Duration::from_secs(60)));
+    let key = |i: usize| ClientKey::Addr(IpAddr::V4(Ipv4Addr::from(i as u32)));
+
+    // None of the buckets is idle, since each client has made a request.
+    for i in 0..MAX_TRACKED_CLIENTS * 2 {
+        assert!(limiter.check(key(i)).is_ok());
+        assert!(limiter.tracked_clients() <= MAX_TRACKED_CLIENTS);
+    }
+
+    // The most recent client keeps its bucket.
+    let last = key(MAX_TRACKED_CLIENTS * 2 - 1);
+    assert!(limiter.check(last.clone()).is_ok());
+    assert!(limiter.check(last).is_err());
+}
+
+#[tokio::test]
+async fn test_rate_limited_endpoint() {
+    let options = EndpointOptions {
+        rate_limit: Some(RateLimiter::new(RateLimit::new(1, Duration::from_secs(60)))),
+        ..EndpointOptions::default()
+    };
+    let filter = simple_post("increment", options, || Ok::<_, Error>(())).recover(handle_rejection);
+
+    let request = || {
+        warp::test::request()
+            .method("POST")
+            .path("/increment")
+            .header("authorization", "alice")
+    };
+
+    let response = request().reply(&filter).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = request().reply(&filter).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()["retry-after"], "60");
+}
+
+#[tokio::test]
+async fn test_rate_limit_ignores_authorization() {
+    let options = EndpointOptions {
+        rate_limit: Some(RateLimiter::new(RateLimit::new(1, Duration::from_secs(60)))),
+        ..EndpointOptions::default()
+    };
+    let filter = simple_post("increment", options, || Ok::<_, Error>(())).recover(handle_rejection);
+
+    // A fresh `Authorization` value does not give the client a fresh bucket.
+    let statuses = [StatusCode::OK, StatusCode::TOO_MANY_REQUESTS];
+    for (i, expected) in statuses.iter().enumerate() {
+        let response = warp::test::request()
+            .method("POST")
+            .path("/increment")
+            .header("authorization", format!("client-{}", i))
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), *expected);
+    }
+}
+
+#[tokio::test]
+async fn test_rate_limit_by_principal() {
+    let options = EndpointOptions {
+        rate_limit: Some(RateLimiter::new(RateLimit::new(1, Duration::from_secs(60)))),
+        ..EndpointOptions::default()
+    };
+    let filter = simple_post("increment", options, || Ok::<_, Error>(())).recover(handle_rejection);
+
+    // The principal is inserted by the authentication layer of the application.
+    let requests = [
+        ("alice", StatusCode::OK),
+        ("bob", StatusCode::OK),
+        ("alice", StatusCode::TOO_MANY_REQUESTS),
+    ];
+    for (principal, expected) in requests.iter() {
+        let response = warp::test::request()
+            .method("POST")
+            .path("/increment")
+            .extension(Principal((*principal).to_owned()))
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), *expected);
+    }
+}
+
+#[tokio::test]
+async fn test_rate_limited_params_endpoint() {
+    let options = EndpointOptions {
+        rate_limit: Some(RateLimiter::new(RateLimit::new(1, Duration::from_secs(60)))),
+        ..EndpointOptions::default()
+    };
+    let filter = params_post("add", options, |value: u64| Ok::<_, Error>(value + 1))
+        .recover(handle_rejection);
+
+    let response = warp::test::request()
+        .method("POST")
+        .path("/add")
+        .json(&1)
+        .reply(&filter)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.body().as_ref(), b"2");
+
+    let response = warp::test::request()
+        .method("POST")
+        .path("/add")
+        .json(&1)
+        .reply(&filter)
+        .await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // The endpoint is served on POST only, as by the actix backend.
+    let response = warp::test::request()
+        .method("GET")
+        .path("/add")
+        .json(&1)
+        .reply(&filter)
+        .await;
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+}
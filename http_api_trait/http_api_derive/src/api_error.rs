@@ -0,0 +1,124 @@
+use darling::{ast, FromDeriveInput, FromVariant};
+use proc_macro::TokenStream;
+use quote::{quote, ToTokens};
+use syn::DeriveInput;
+
+#[derive(Debug, FromDeriveInput)]
+#[darling(supports(enum_any))]
+struct ApiError {
+    ident: syn::Ident,
+    generics: syn::Generics,
+    data: ast::Data<ApiErrorVariant, ()>,
+}
+
+#[derive(Debug, FromVariant)]
+#[darling(attributes(api_error))]
+struct ApiErrorVariant {
+    ident: syn::Ident,
+    #[darling(default)]
+    status: Option<u16>,
+    #[darling(default)]
+    code: Option<String>,
+}
+
+impl ApiErrorVariant {
+    fn status(&self) -> u16 {
+        self.status.unwrap_or(500)
+    }
+
+    /// Error code, by default it is the variant name in the `snake_case`.
+    fn code(&self) -> String {
+        self.code
+            .clone()
+            .unwrap_or_else(|| snake_case(&self.ident.to_string()))
+    }
+
+    fn validate(&self) -> Result<(), darling::Error> {
+        match self.status {
+            Some(status) if !(400..600).contains(&status) => Err(darling::Error::custom(
+                "Error status should be in the `400..600` range",
+            )
+            .with_span(&self.ident)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Converts the `CamelCase` name into the `snake_case`, the acronyms are kept as single words,
+/// e.g. `HTTPError` becomes `http_error`.
+fn snake_case(name: &str) -> String {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut out = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if !prev.is_uppercase() || next_is_lower {
+                out.push('_');
+            }
+        }
+        out.extend(c.to_lowercase());
+    }
+    out
+}
+
+impl ToTokens for ApiError {
+    fn to_tokens(&self, out: &mut proc_macro2::TokenStream) {
+        let ident = &self.ident;
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+        let variants = self.data.as_ref().take_enum().unwrap();
+
+        let status_arms = variants.iter().map(|variant| {
+            let variant_ident = &variant.ident;
+            let status = variant.status();
+            quote! { #ident::#variant_ident { .. } => #status, }
+        });
+        let code_arms = variants.iter().map(|variant| {
+            let variant_ident = &variant.ident;
+            let code = variant.code();
+            quote! { #ident::#variant_ident { .. } => #code, }
+        });
+
+        let tokens = quote! {
+            impl #impl_generics ::http_api::export::warp::reject::Reject for #ident #ty_generics #where_clause {}
+
+            impl #impl_generics http_api::ApiError for #ident #ty_generics #where_clause {
+                fn status(&self) -> u16 {
+                    match *self {
+                        #( #status_arms )*
+                    }
+                }
+
+                fn code(&self) -> &'static str {
+                    match *self {
+                        #( #code_arms )*
+                    }
+                }
+            }
+        };
+        out.extend(tokens)
+    }
+}
+
+pub fn impl_api_error(input: TokenStream) -> TokenStream {
+    let input: DeriveInput = syn::parse(input).unwrap();
+    let api_error = match ApiError::from_derive_input(&input) {
+        Ok(parsed) => parsed,
+        Err(e) => return e.write_errors().into(),
+    };
+
+    let errors = api_error
+        .data
+        .as_ref()
+        .take_enum()
+        .unwrap()
+        .into_iter()
+        .filter_map(|variant| variant.validate().err())
+        .collect::<Vec<_>>();
+    if !errors.is_empty() {
+        return darling::Error::multiple(errors).write_errors().into();
+    }
+
+    let tokens = quote! { #api_error };
+    tokens.into()
+}
@@ -35,9 +35,109 @@ impl FromMeta for SupportedHttpMethod {
     }
 }
 
+/// Parses durations like `30s`, `5m` or `h`; the missing amount is treated as one.
+fn parse_duration_secs(value: &str) -> Option<u64> {
+    let unit_pos = value.find(|c: char| !c.is_ascii_digit())?;
+    let (amount, unit) = value.split_at(unit_pos);
+    let amount = if amount.is_empty() {
+        1
+    } else {
+        amount.parse::<u64>().ok()?
+    };
+
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return None,
+    };
+    amount.checked_mul(multiplier).filter(|secs| *secs > 0)
+}
+
+/// Rate limit in the `requests/period` form, for example `10/s` or `100/5m`.
+#[derive(Debug)]
+struct RateLimitAttr {
+    requests: u32,
+    period_secs: u64,
+}
+
+impl FromMeta for RateLimitAttr {
+    fn from_string(value: &str) -> Result<Self, darling::Error> {
+        let invalid_value = || {
+            darling::Error::custom(
+                "Rate limit should be specified in the `requests/period` form, e.g. `10/s`",
+            )
+        };
+
+        let mut parts = value.splitn(2, '/');
+        let requests = parts
+            .next()
+            .and_then(|requests| requests.parse::<u32>().ok())
+            .filter(|requests| *requests > 0)
+            .ok_or_else(invalid_value)?;
+        let period_secs = parts
+            .next()
+            .and_then(parse_duration_secs)
+            .ok_or_else(invalid_value)?;
+
+        Ok(Self {
+            requests,
+            period_secs,
+        })
+    }
+}
+
+impl ToTokens for RateLimitAttr {
+    fn to_tokens(&self, out: &mut proc_macro2::TokenStream) {
+        let requests = self.requests;
+        let period_secs = self.period_secs;
+
+        out.extend(quote! {
+            http_api::rate_limit::RateLimiter::new(http_api::rate_limit::RateLimit::new(
+                #requests,
+                std::time::Duration::from_secs(#period_secs),
+            ))
+        })
+    }
+}
+
+/// Lifetime of the cached endpoint response, for example `30s` or `5m`.
+#[derive(Debug)]
+struct CacheTtlAttr {
+    secs: u64,
+}
+
+impl FromMeta for CacheTtlAttr {
+    fn from_string(value: &str) -> Result<Self, darling::Error> {
+        parse_duration_secs(value)
+            .map(|secs| Self { secs })
+            .ok_or_else(|| {
+                darling::Error::custom(
+                    "Cache TTL should be specified as a duration, e.g. `30s` or `5m`",
+                )
+            })
+    }
+}
+
+impl ToTokens for CacheTtlAttr {
+    fn to_tokens(&self, out: &mut proc_macro2::TokenStream) {
+        let secs = self.secs;
+
+        out.extend(quote! {
+            http_api::cache::ResponseCache::new(std::time::Duration::from_secs(#secs))
+        })
+    }
+}
+
 #[derive(Debug, FromMeta)]
 struct ApiAttrs {
-    warp: syn::Ident,
+    #[darling(default)]
+    warp: Option<syn::Ident>,
+    #[darling(default)]
+    jsonrpc: Option<syn::Ident>,
+    #[darling(default)]
+    actix: Option<syn::Ident>,
 }
 
 #[derive(Debug, FromMeta)]
@@ -45,13 +145,16 @@ struct EndpointAttrs {
     method: SupportedHttpMethod,
     #[darling(default)]
     rename: Option<String>,
+    #[darling(default)]
+    rate_limit: Option<RateLimitAttr>,
+    #[darling(default)]
+    cache_ttl: Option<CacheTtlAttr>,
 }
 
 #[derive(Debug)]
 struct ParsedEndpoint {
     ident: syn::Ident,
     arg: Option<Box<syn::Type>>,
-    ret: Box<syn::Type>,
     attrs: EndpointAttrs,
 }
 
@@ -84,21 +187,25 @@ impl ParsedEndpoint {
             })
             .transpose()?;
 
-        // Extract return type.
-        let ret = match &sig.output {
-            syn::ReturnType::Type(_, ty) => Ok(ty.clone()),
-            _ => unreachable!("Only first argument can be receiver."),
-        }?;
+        // Check return type.
+        if let syn::ReturnType::Default = sig.output {
+            return Err(invalid_method(&sig));
+        }
 
         // Extract attributes.
         let attrs = find_meta_attrs("http_api_endpoint", attrs)
             .map(|meta| EndpointAttrs::from_nested_meta(&meta))
             .unwrap_or_else(|| Err(darling::Error::custom("todo")))?;
 
+        if let (SupportedHttpMethod::Post, Some(_)) = (&attrs.method, &attrs.cache_ttl) {
+            return Err(
+                darling::Error::custom("Only GET endpoints can cache responses").with_span(&sig),
+            );
+        }
+
         Ok(Self {
             ident: sig.ident.clone(),
             arg,
-            ret,
             attrs,
         })
     }
@@ -110,46 +217,105 @@ impl ParsedEndpoint {
             .unwrap_or_else(|| self.ident.to_string())
     }
 
-    fn impl_endpoint_handler(&self) -> impl ToTokens {
-        let path = self.endpoint_path();
-        let ident = &self.ident;
+    fn impl_endpoint_options(&self) -> impl ToTokens {
+        let rate_limit = match &self.attrs.rate_limit {
+            Some(rate_limit) => quote! { Some(#rate_limit) },
+            None => quote! { None },
+        };
+        let cache = match &self.attrs.cache_ttl {
+            Some(cache_ttl) => quote! { Some(#cache_ttl) },
+            None => quote! { None },
+        };
 
-        match (&self.attrs.method, &self.arg) {
-            (SupportedHttpMethod::Get, None) => {
-                quote! {
-                    let #ident = http_api::warp_backend::simple_get(#path, {
-                        let out = service.clone();
-                        move || out.#ident()
-                    });
-                }
+        quote! {
+            http_api::EndpointOptions {
+                rate_limit: #rate_limit,
+                cache: #cache,
             }
+        }
+    }
 
-            (SupportedHttpMethod::Get, Some(_arg)) => {
-                quote! {
-                    let #ident = http_api::warp_backend::query_get(#path, {
-                        let out = service.clone();
-                        move |query| out.#ident(query)
-                    });
-                }
-            }
+    fn impl_jsonrpc_method(&self) -> impl ToTokens {
+        let method = self.endpoint_path();
+        let ident = &self.ident;
 
-            (SupportedHttpMethod::Post, None) => {
-                quote! {
-                    let #ident = http_api::warp_backend::simple_post(#path, {
-                        let out = service.clone();
-                        move || out.#ident()
-                    });
+        let convert = convert_api_error();
+        match &self.arg {
+            None => quote! {
+                #method => http_api::jsonrpc::to_result(service.#ident().map_err(#convert)),
+            },
+            Some(_arg) => quote! {
+                #method => {
+                    let params = http_api::jsonrpc::parse_params(params)?;
+                    http_api::jsonrpc::to_result(service.#ident(params).map_err(#convert))
                 }
-            }
+            },
+        }
+    }
 
-            (SupportedHttpMethod::Post, Some(_arg)) => {
-                quote! {
-                    let #ident = http_api::warp_backend::params_post(#path, {
-                        let out = service.clone();
-                        move |params| out.#ident(params)
-                    });
-                }
+    /// Returns the name of the backend function that mounts this endpoint and the handler for it.
+    fn endpoint_handler(&self) -> (syn::Ident, impl ToTokens) {
+        let ident = &self.ident;
+
+        let convert = convert_api_error();
+        let (backend_fn, handler) = match (&self.attrs.method, &self.arg) {
+            (SupportedHttpMethod::Get, None) => (
+                "simple_get",
+                quote! { move || out.#ident().map_err(#convert) },
+            ),
+            (SupportedHttpMethod::Get, Some(_arg)) => (
+                "query_get",
+                quote! { move |query| out.#ident(query).map_err(#convert) },
+            ),
+            (SupportedHttpMethod::Post, None) => (
+                "simple_post",
+                quote! { move || out.#ident().map_err(#convert) },
+            ),
+            (SupportedHttpMethod::Post, Some(_arg)) => (
+                "params_post",
+                quote! { move |params| out.#ident(params).map_err(#convert) },
+            ),
+        };
+
+        let backend_fn = syn::Ident::new(backend_fn, proc_macro2::Span::call_site());
+        let handler = quote! {
+            {
+                let out = service.clone();
+                #handler
             }
+        };
+        (backend_fn, handler)
+    }
+
+    fn impl_warp_filter(&self) -> impl ToTokens {
+        let path = self.endpoint_path();
+        let ident = &self.ident;
+        let options = self.impl_endpoint_options();
+        let (backend_fn, handler) = self.endpoint_handler();
+
+        quote! {
+            let #ident = http_api::warp_backend::#backend_fn(#path, #options, #handler);
+        }
+    }
+
+    fn impl_actix_route(&self) -> impl ToTokens {
+        let path = self.endpoint_path();
+        let options = self.impl_endpoint_options();
+        let (backend_fn, handler) = self.endpoint_handler();
+
+        quote! {
+            http_api::actix_backend::#backend_fn(config, #path, #options, #handler);
+        }
+    }
+}
+
+/// Closure converting the endpoint errors implementing `ApiError` into `ErrorReply`,
+/// the other errors are left intact.
+fn convert_api_error() -> impl ToTokens {
+    quote! {
+        |e| {
+            use http_api::kind::{ApiKind as _, OtherKind as _};
+            (&e).api_error_kind().convert(e)
         }
     }
 }
@@ -181,6 +347,11 @@ impl ParsedApiDefinition {
 
         // Extract attributes.
         let attrs = ApiAttrs::from_list(attrs)?;
+        if attrs.warp.is_none() && attrs.jsonrpc.is_none() && attrs.actix.is_none() {
+            return Err(darling::Error::custom(
+                "At least one of `warp`, `jsonrpc` or `actix` attributes should be specified",
+            ));
+        }
 
         Ok(Self {
             item_trait,
@@ -190,9 +361,8 @@ impl ParsedApiDefinition {
     }
 }
 
-impl ToTokens for ParsedApiDefinition {
-    fn to_tokens(&self, out: &mut proc_macro2::TokenStream) {
-        let fn_name = &self.attrs.warp;
+impl ParsedApiDefinition {
+    fn impl_warp_serve(&self, fn_name: &syn::Ident) -> impl ToTokens {
         let interface = &self.item_trait.ident;
 
         let (filters, idents): (Vec<_>, Vec<_>) = self
@@ -200,7 +370,7 @@ impl ToTokens for ParsedApiDefinition {
             .iter()
             .map(|endpoint| {
                 let ident = &endpoint.ident;
-                let handler = endpoint.impl_endpoint_handler();
+                let handler = endpoint.impl_warp_filter();
 
                 (handler, ident)
             })
@@ -212,7 +382,7 @@ impl ToTokens for ParsedApiDefinition {
             #head #( .or(#tail) )*
         };
 
-        let tokens = quote! {
+        quote! {
             fn #fn_name<T>(
                 service: T,
                 addr: impl Into<std::net::SocketAddr>,
@@ -224,11 +394,78 @@ impl ToTokens for ParsedApiDefinition {
 
                 #( #filters )*
 
-                warp::serve(#serve_impl).run(addr.into())
+                let routes = (#serve_impl).recover(http_api::warp_backend::handle_rejection);
+                warp::serve(routes).run(addr.into())
             }
+        }
+    }
 
-        };
-        out.extend(tokens)
+    fn impl_actix_configure(&self, fn_name: &syn::Ident) -> impl ToTokens {
+        let interface = &self.item_trait.ident;
+        let routes = self.endpoints.iter().map(ParsedEndpoint::impl_actix_route);
+
+        quote! {
+            fn #fn_name<T>(service: T, config: &mut actix_web::web::ServiceConfig)
+            where
+                T: #interface + Clone + 'static,
+            {
+                #( #routes )*
+            }
+        }
+    }
+
+    fn impl_jsonrpc_serve(&self, fn_name: &syn::Ident) -> impl ToTokens {
+        let interface = &self.item_trait.ident;
+        let methods = self
+            .endpoints
+            .iter()
+            .map(ParsedEndpoint::impl_jsonrpc_method);
+        let (names, options): (Vec<_>, Vec<_>) = self
+            .endpoints
+            .iter()
+            .map(|endpoint| (endpoint.endpoint_path(), endpoint.impl_endpoint_options()))
+            .unzip();
+
+        quote! {
+            fn #fn_name<T>(
+                service: T,
+                addr: impl Into<std::net::SocketAddr>,
+            ) -> impl std::future::Future<Output = ()>
+            where
+                T: #interface + Clone + Send + Sync + 'static,
+            {
+                #[allow(unused_variables)]
+                let dispatch = move |
+                    method: &str,
+                    params: Option<http_api::export::serde_json::Value>,
+                | -> Result<http_api::export::serde_json::Value, http_api::jsonrpc::Error> {
+                    match method {
+                        #( #methods )*
+                        _ => Err(http_api::jsonrpc::Error::method_not_found()),
+                    }
+                };
+
+                let mut options = std::collections::HashMap::new();
+                #( options.insert(#names, #options); )*
+
+                warp::serve(http_api::warp_backend::jsonrpc_with_options(dispatch, options))
+                    .run(addr.into())
+            }
+        }
+    }
+}
+
+impl ToTokens for ParsedApiDefinition {
+    fn to_tokens(&self, out: &mut proc_macro2::TokenStream) {
+        if let Some(fn_name) = &self.attrs.warp {
+            self.impl_warp_serve(fn_name).to_tokens(out);
+        }
+        if let Some(fn_name) = &self.attrs.jsonrpc {
+            self.impl_jsonrpc_serve(fn_name).to_tokens(out);
+        }
+        if let Some(fn_name) = &self.attrs.actix {
+            self.impl_actix_configure(fn_name).to_tokens(out);
+        }
     }
 }
 
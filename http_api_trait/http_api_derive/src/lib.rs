@@ -2,6 +2,7 @@ extern crate proc_macro;
 
 use proc_macro::TokenStream;
 
+mod api_error;
 mod from_url_query;
 mod http_api;
 
@@ -10,6 +11,11 @@ pub fn from_url_query(input: TokenStream) -> TokenStream {
     from_url_query::impl_from_url_query(input)
 }
 
+#[proc_macro_derive(ApiError, attributes(api_error))]
+pub fn api_error(input: TokenStream) -> TokenStream {
+    api_error::impl_api_error(input)
+}
+
 #[proc_macro_attribute]
 pub fn http_api(attr: TokenStream, item: TokenStream) -> TokenStream {
     http_api::impl_http_api(attr, item)